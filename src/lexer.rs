@@ -1,9 +1,17 @@
+#[cfg(feature = "bignum")]
+pub mod bignum;
 pub mod callable;
+pub mod clock;
 pub mod environment;
+pub mod error;
 pub mod expr;
+pub mod filesystem;
 pub mod function;
 pub mod interpreter;
+pub mod output;
 pub mod parser;
+pub mod random;
 pub mod scanner;
 pub mod stmt;
 pub mod token;
+pub mod trace;
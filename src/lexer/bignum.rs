@@ -0,0 +1,85 @@
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+
+use super::{
+    error::{ErrorCode, RuntimeError},
+    expr::ExprLiteral,
+    token::{Token, TokenType},
+};
+
+// Result of looking at a binary expression from the BigInt side.
+pub enum Binary {
+    Done(ExprLiteral), // Both operands are BigInt and the operator is handled here.
+    Operands(ExprLiteral, ExprLiteral), // Leave it to the normal f64 / string arms.
+}
+
+// brief: Evaluate BigInt (op) BigInt exactly, and promote a mixed BigInt/f64 pair to f64.
+// input:
+// output:
+pub fn binary(
+    left: ExprLiteral,
+    operator: &Token,
+    right: ExprLiteral,
+) -> Result<Binary, RuntimeError> {
+    match (left, right) {
+        (ExprLiteral::BigIntLiteral(l), ExprLiteral::BigIntLiteral(r)) => {
+            let value = match operator.token_type {
+                TokenType::Plus => ExprLiteral::BigIntLiteral(l + r),
+                TokenType::Minus => ExprLiteral::BigIntLiteral(l - r),
+                TokenType::Star => ExprLiteral::BigIntLiteral(l * r),
+                TokenType::Slash => {
+                    if r.is_zero() {
+                        return Err(RuntimeError::new(
+                            ErrorCode::DivisionByZero,
+                            format!(
+                                "Error occur when interpreter at line {} at {} for dividing BigInt by zero.",
+                                operator.line_number, operator.lexeme
+                            ),
+                        ));
+                    }
+                    ExprLiteral::BigIntLiteral(l / r) // Truncated like integer division.
+                }
+                TokenType::Greater => from_bool(l > r),
+                TokenType::GreaterEqual => from_bool(l >= r),
+                TokenType::Less => from_bool(l < r),
+                TokenType::LessEqual => from_bool(l <= r),
+                _ => {
+                    return Ok(Binary::Operands(
+                        ExprLiteral::BigIntLiteral(l),
+                        ExprLiteral::BigIntLiteral(r),
+                    ))
+                }
+            };
+            Ok(Binary::Done(value))
+        }
+        (ExprLiteral::BigIntLiteral(l), r @ ExprLiteral::NumberLiteral(_)) => {
+            Ok(Binary::Operands(to_float(&l, operator)?, r))
+        }
+        (l @ ExprLiteral::NumberLiteral(_), ExprLiteral::BigIntLiteral(r)) => {
+            Ok(Binary::Operands(l, to_float(&r, operator)?))
+        }
+        (l, r) => Ok(Binary::Operands(l, r)),
+    }
+}
+
+// brief: Convert a BigInt to NumberLiteral, so that it can be mixed with f64.
+// input:
+// output:
+fn to_float(value: &BigInt, operator: &Token) -> Result<ExprLiteral, RuntimeError> {
+    match value.to_f64() {
+        Some(v) => Ok(ExprLiteral::NumberLiteral(v)),
+        None => Err(format!(
+            "Error occur when interpreter at line {} at {} for BigInt out of f64 range.",
+            operator.line_number, operator.lexeme
+        )
+        .into()),
+    }
+}
+
+fn from_bool(value: bool) -> ExprLiteral {
+    if value {
+        ExprLiteral::True
+    } else {
+        ExprLiteral::False
+    }
+}
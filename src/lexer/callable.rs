@@ -1,17 +1,36 @@
-use std::time::UNIX_EPOCH;
-use std::{fmt::Debug, time::SystemTime};
+use std::{fmt::Debug, time::Duration};
 
-use super::{expr::ExprLiteral, interpreter::Interpreter};
+use super::{
+    clock::format_timestamp,
+    error::{ErrorCode, RuntimeError},
+    expr::ExprLiteral,
+    interpreter::Interpreter,
+    random::Rng,
+    token::Token,
+};
 
 pub trait Callable: Debug {
     fn call(
         &mut self,
         interpreter: &mut Interpreter,
         arguments: Vec<ExprLiteral>,
-    ) -> Result<ExprLiteral, String>;
+    ) -> Result<ExprLiteral, RuntimeError>;
 
     fn arity(&self) -> usize;
 
+    // Whether calling with this many arguments is fine, partial applied functions take fewer.
+    fn accepts(&self, count: usize) -> bool {
+        count == self.arity()
+    }
+
+    fn name(&self) -> String; // used by two_string() and the call stack.
+
+    // Whether `lazy` arguments may be passed unforced, the parameters of script functions force
+    // them when read. Natives get values.
+    fn lazy_arguments(&self) -> bool {
+        false
+    }
+
     fn clone_box(&self) -> Box<dyn Callable>;
 }
 
@@ -22,52 +41,564 @@ impl Clone for Box<dyn Callable> {
 }
 
 impl PartialEq for Box<dyn Callable> {
-    fn eq(&self, other: &Self) -> bool {
+    fn eq(&self, _other: &Self) -> bool {
         false // Todo
     }
 }
 
 #[derive(Debug, Copy, Clone)]
-pub struct MyCallable;
+pub struct MyClock;
 
-impl Callable for MyCallable {
+impl Callable for MyClock {
     fn call(
         &mut self,
         interpreter: &mut Interpreter,
+        _arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        Ok(ExprLiteral::NumberLiteral(interpreter.clock().now()))
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn name(&self) -> String {
+        "clock".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MyAbs;
+
+impl Callable for MyAbs {
+    fn call(
+        &mut self,
+        _interpreter: &mut Interpreter,
         arguments: Vec<ExprLiteral>,
-    ) -> Result<ExprLiteral, String> {
-        todo!()
+    ) -> Result<ExprLiteral, RuntimeError> {
+        match arguments[0] {
+            ExprLiteral::NumberLiteral(v) => Ok(ExprLiteral::NumberLiteral(v.abs())),
+            _ => Err(wrong_argument(format!(
+                "Error occur , abs expect a number, but got {}.",
+                arguments[0].two_string()
+            ))),
+        }
     }
 
     fn arity(&self) -> usize {
-        todo!()
+        1
     }
 
+    fn name(&self) -> String {
+        "abs".to_string()
+    }
     fn clone_box(&self) -> Box<dyn Callable> {
         Box::new(*self)
     }
 }
 
 #[derive(Debug, Copy, Clone)]
-pub struct MyClock;
+pub struct MyToString;
 
-impl Callable for MyClock {
+impl Callable for MyToString {
+    fn call(
+        &mut self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        Ok(ExprLiteral::StringLiteral(arguments[0].two_string()))
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn name(&self) -> String {
+        "to_string".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MyCurry;
+
+impl Callable for MyCurry {
+    fn call(
+        &mut self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        match &arguments[0] {
+            ExprLiteral::FunctionLiteral(f) => {
+                Ok(ExprLiteral::FunctionLiteral(Box::new(MyCurried {
+                    target: f.clone(),
+                    bound: vec![],
+                })))
+            }
+            other => Err(wrong_argument(format!(
+                "Error occur , curry expect a function, but got {}.",
+                other.two_string()
+            ))),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn name(&self) -> String {
+        "curry".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+// A function with some of its arguments already given, returned by curry().
+#[derive(Debug, Clone)]
+pub struct MyCurried {
+    target: Box<dyn Callable>,
+    bound: Vec<ExprLiteral>,
+}
+
+impl Callable for MyCurried {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let mut bound = self.bound.clone();
+        bound.extend(arguments);
+
+        if bound.len() < self.target.arity() {
+            return Ok(ExprLiteral::FunctionLiteral(Box::new(MyCurried {
+                target: self.target.clone(),
+                bound,
+            })));
+        }
+        self.target.call(interpreter, bound)
+    }
+
+    fn arity(&self) -> usize {
+        self.target.arity() - self.bound.len() // the arguments still missing.
+    }
+
+    // At least one argument per call, unless the target takes none, then curry(f)() calls it.
+    fn accepts(&self, count: usize) -> bool {
+        (count >= 1 || self.arity() == 0) && count <= self.arity()
+    }
+
+    fn lazy_arguments(&self) -> bool {
+        self.target.lazy_arguments()
+    }
+
+    fn name(&self) -> String {
+        self.target.name()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MyReadFile;
+
+impl Callable for MyReadFile {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let line = interpreter.call_line();
+        let path = string_argument(&arguments[0], "read_file", line)?;
+        match interpreter.filesystem().read(&path) {
+            Ok(contents) => Ok(ExprLiteral::StringLiteral(contents)),
+            Err(msg) => Err(format!(
+                "Error occur when interpreter at line {} at read_file for {}.",
+                line, msg
+            )
+            .into()),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn name(&self) -> String {
+        "read_file".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MyWriteFile;
+
+impl Callable for MyWriteFile {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let line = interpreter.call_line();
+        let path = string_argument(&arguments[0], "write_file", line)?;
+        let contents = string_argument(&arguments[1], "write_file", line)?;
+        match interpreter.filesystem().write(&path, &contents) {
+            Ok(()) => Ok(ExprLiteral::Nil),
+            Err(msg) => Err(format!(
+                "Error occur when interpreter at line {} at write_file for {}.",
+                line, msg
+            )
+            .into()),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn name(&self) -> String {
+        "write_file".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+// brief: The error for an argument a native can't take, like abs("a").
+// input:
+// output:
+fn wrong_argument(message: String) -> RuntimeError {
+    RuntimeError::new(ErrorCode::TypeMismatch, message)
+}
+
+// brief: Path and contents of the file natives have to be strings.
+// input:
+// output:
+fn string_argument(
+    argument: &ExprLiteral,
+    name: &str,
+    line: usize,
+) -> Result<String, RuntimeError> {
+    match argument {
+        ExprLiteral::StringLiteral(v) => Ok(v.clone()),
+        other => Err(wrong_argument(format!(
+            "Error occur , {} expect a string, but got {} at line: {}.",
+            name,
+            other.two_string(),
+            line
+        ))),
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MySeed;
+
+impl Callable for MySeed {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        match arguments[0] {
+            ExprLiteral::NumberLiteral(v) => {
+                *interpreter.rng() = Rng::new(v.to_bits());
+                Ok(ExprLiteral::Nil)
+            }
+            _ => Err(wrong_argument(format!(
+                "Error occur , seed expect a number, but got {} at line: {}.",
+                arguments[0].two_string(),
+                interpreter.call_line()
+            ))),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn name(&self) -> String {
+        "seed".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MyRandom;
+
+impl Callable for MyRandom {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        _arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        Ok(ExprLiteral::NumberLiteral(interpreter.rng().next_f64()))
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn name(&self) -> String {
+        "random".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+// randint(a, b) gives a whole number in [a, b], both ends included.
+#[derive(Debug, Copy, Clone)]
+pub struct MyRandInt;
+
+impl Callable for MyRandInt {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let line = interpreter.call_line();
+        let (low, high) = match (&arguments[0], &arguments[1]) {
+            (ExprLiteral::NumberLiteral(a), ExprLiteral::NumberLiteral(b))
+                if a.fract() == 0.0 && b.fract() == 0.0 && a <= b =>
+            {
+                (*a, *b)
+            }
+            _ => {
+                return Err(wrong_argument(format!(
+                    "Error occur , randint expect two whole numbers a <= b, but got {} and {} at line: {}.",
+                    arguments[0].two_string(),
+                    arguments[1].two_string(),
+                    line
+                )))
+            }
+        };
+        let span = high - low + 1.0;
+        let offset = (interpreter.rng().next_f64() * span)
+            .floor()
+            .min(span - 1.0);
+        Ok(ExprLiteral::NumberLiteral(low + offset))
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn name(&self) -> String {
+        "randint".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+// assert_approx(a, b, eps) passes when |a - b| <= eps, since floats rarely compare equal exactly.
+#[derive(Debug, Copy, Clone)]
+pub struct MyAssertApprox;
+
+impl Callable for MyAssertApprox {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let line = interpreter.call_line();
+        let (a, b, eps) = match (&arguments[0], &arguments[1], &arguments[2]) {
+            (
+                ExprLiteral::NumberLiteral(a),
+                ExprLiteral::NumberLiteral(b),
+                ExprLiteral::NumberLiteral(eps),
+            ) if *eps >= 0.0 => (*a, *b, *eps),
+            _ => {
+                return Err(wrong_argument(format!(
+                    "Error occur , assert_approx expect two numbers and a tolerance >= 0, but got {}, {} and {} at line: {}.",
+                    arguments[0].two_string(),
+                    arguments[1].two_string(),
+                    arguments[2].two_string(),
+                    line
+                )))
+            }
+        };
+        let difference = (a - b).abs();
+        let failure = if difference <= eps {
+            None
+        } else {
+            Some(format!(
+                "Assertion failed at line {}: assert_approx({}, {}, {}), the difference is {}.",
+                line, a, b, eps, difference
+            ))
+        };
+        interpreter.report_assertion(failure)?;
+        Ok(ExprLiteral::Nil)
+    }
+
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn name(&self) -> String {
+        "assert_approx".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+// An operator used as a value like (+), calling it applies the operator to the two arguments.
+#[derive(Debug, Clone)]
+pub struct MyOperator {
+    operator: Token,
+}
+
+impl MyOperator {
+    pub fn new(operator: Token) -> Self {
+        Self { operator }
+    }
+}
+
+impl Callable for MyOperator {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        interpreter.binary_operation(arguments[0].clone(), &self.operator, arguments[1].clone())
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn name(&self) -> String {
+        self.operator.lexeme.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(self.clone())
+    }
+}
+
+// format_currency(1234.5, "$") gives "$1,234.50", a negative amount gives "-$1.00".
+#[derive(Debug, Copy, Clone)]
+pub struct MyFormatCurrency;
+
+impl Callable for MyFormatCurrency {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let line = interpreter.call_line();
+        let amount = match arguments[0] {
+            ExprLiteral::NumberLiteral(v) if v.is_finite() => v,
+            _ => {
+                return Err(wrong_argument(format!(
+                    "Error occur , format_currency expect a finite number, but got {} at line: {}.",
+                    arguments[0].two_string(),
+                    line
+                )))
+            }
+        };
+        let symbol = string_argument(&arguments[1], "format_currency", line)?;
+
+        let digits = format!("{:.2}", amount.abs()); // rounded before grouping, 999.999 is 1,000.00.
+        let (whole, cents) = digits.split_at(digits.len() - 3);
+        let mut grouped = String::new();
+        for (index, c) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(c);
+        }
+        // No "-$0.00" for a tiny negative amount.
+        let sign = if amount < 0.0 && digits.bytes().any(|b| (b'1'..=b'9').contains(&b)) {
+            "-"
+        } else {
+            ""
+        };
+        Ok(ExprLiteral::StringLiteral(format!(
+            "{}{}{}{}",
+            sign, symbol, grouped, cents
+        )))
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn name(&self) -> String {
+        "format_currency".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MySleep;
+
+impl Callable for MySleep {
     fn call(
         &mut self,
         interpreter: &mut Interpreter,
         arguments: Vec<ExprLiteral>,
-    ) -> Result<ExprLiteral, String> {
-        let start = SystemTime::now();
-        let since_the_epoch = start
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards");
+    ) -> Result<ExprLiteral, RuntimeError> {
+        match arguments[0] {
+            // The clock waits with a Duration, so a number too big for it is a wrong argument too.
+            ExprLiteral::NumberLiteral(v) if Duration::try_from_secs_f64(v).is_ok() => {
+                interpreter.clock().sleep(v);
+                Ok(ExprLiteral::Nil)
+            }
+            _ => Err(wrong_argument(format!(
+                "Error occur , sleep expect seconds >= 0 which fit in a Duration, but got {} at line: {}.",
+                arguments[0].two_string(),
+                interpreter.call_line()
+            ))),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
 
-        Ok(ExprLiteral::NumberLiteral(since_the_epoch.as_secs_f64()))
+    fn name(&self) -> String {
+        "sleep".to_string()
+    }
+    fn clone_box(&self) -> Box<dyn Callable> {
+        Box::new(*self)
+    }
+}
+
+// now() gives the time like "2024-01-31 08:05:00" in UTC.
+#[derive(Debug, Copy, Clone)]
+pub struct MyNow;
+
+impl Callable for MyNow {
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        _arguments: Vec<ExprLiteral>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        Ok(ExprLiteral::StringLiteral(format_timestamp(
+            interpreter.clock().now(),
+        )))
     }
 
     fn arity(&self) -> usize {
         0
     }
+
+    fn name(&self) -> String {
+        "now".to_string()
+    }
     fn clone_box(&self) -> Box<dyn Callable> {
         Box::new(*self)
     }
@@ -0,0 +1,88 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Where clock(), now() and sleep() get the time from, so that tests don't really wait.
+pub trait Clock {
+    fn now(&self) -> f64; // seconds since the unix epoch.
+
+    fn sleep(&mut self, seconds: f64);
+}
+
+// The real time.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64())
+    }
+
+    fn sleep(&mut self, seconds: f64) {
+        thread::sleep(Duration::from_secs_f64(seconds));
+    }
+}
+
+// A time which only moves by sleep(), clones share it so the caller can look at it afterwards.
+#[derive(Debug, Default, Clone)]
+pub struct FakeClock {
+    seconds: Rc<Cell<f64>>,
+}
+
+impl FakeClock {
+    pub fn new(seconds: f64) -> Self {
+        Self {
+            seconds: Rc::new(Cell::new(seconds)),
+        }
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> f64 {
+        self.seconds.get()
+    }
+
+    fn sleep(&mut self, seconds: f64) {
+        self.seconds.set(self.seconds.get() + seconds);
+    }
+}
+
+// brief: Format seconds since the unix epoch as "YYYY-MM-DD HH:MM:SS" in UTC, the fraction is dropped.
+// input:
+// output:
+pub fn format_timestamp(seconds: f64) -> String {
+    let seconds = seconds.floor() as i64;
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400);
+
+    // Days to a civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153; // March is 0.
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
@@ -1,5 +1,12 @@
-use super::{expr::ExprLiteral, token::Token};
-use std::collections::{btree_map::OccupiedEntry, hash_map::VacantEntry, HashMap};
+use super::{
+    error::{ErrorCode, RuntimeError},
+    expr::ExprLiteral,
+    token::Token,
+};
+use std::collections::{BTreeMap, HashMap};
+
+// `_` can be bound and assigned to, the value is dropped. Reading it is rejected by the Parser.
+pub const DISCARD: &str = "_";
 
 #[derive(Debug, Clone)]
 pub struct Environment {
@@ -16,35 +23,86 @@ impl Environment {
     }
 
     pub fn define(&mut self, name: String, value: ExprLiteral) {
+        if name == DISCARD {
+            return;
+        }
         self.values.insert(name, value);
     }
 
-    pub fn get(&self, name: &Token) -> Result<ExprLiteral, String> {
+    // brief: All the variables visible from this scope sorted by name, inner ones shadow the outer ones.
+    // input:
+    // output:
+    pub fn variables(&self) -> Vec<(String, ExprLiteral)> {
+        let mut variables = BTreeMap::new();
+        self.collect_variables(&mut variables);
+        variables.into_iter().collect()
+    }
+
+    fn collect_variables(&self, variables: &mut BTreeMap<String, ExprLiteral>) {
+        if let Some(v) = &self.enclosing {
+            v.collect_variables(variables);
+        }
+        for (name, value) in &self.values {
+            variables.insert(name.clone(), value.clone());
+        }
+    }
+
+    pub fn get(&self, name: &Token) -> Result<ExprLiteral, RuntimeError> {
         match self.values.get(&name.lexeme) {
             Some(v) => Ok(v.clone()),
             None => {
                 if let Some(v) = &self.enclosing {
                     v.get(name)
                 } else {
-                    Err(format!("Undefined variable {}.", name.lexeme))
+                    Err(undefined(name))
                 }
             }
         }
     }
 
-    pub fn assign(&mut self, name: &Token, value: ExprLiteral) -> Result<(), String> {
+    // brief: Remove a variable from the nearest scope which has it.
+    // input:
+    // output:
+    pub fn undefine(&mut self, name: &Token) -> Result<(), RuntimeError> {
+        if self.values.remove(&name.lexeme).is_some() {
+            return Ok(());
+        }
+        match &mut self.enclosing {
+            Some(v) => v.undefine(name),
+            None => Err(undefined(name)),
+        }
+    }
+
+    // brief: Change an existing variable.
+    // input:
+    // output: The value it had before, for the watchers of the Interpreter.
+    pub fn assign(
+        &mut self,
+        name: &Token,
+        value: ExprLiteral,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        if name.lexeme == DISCARD {
+            return Ok(ExprLiteral::Nil);
+        }
         match self.values.get_mut(&name.lexeme) {
-            Some(v) => {
-                *v = value;
-                Ok(())
-            }
+            Some(v) => Ok(std::mem::replace(v, value)),
             None => {
                 if let Some(v) = &mut self.enclosing {
                     v.assign(name, value)
                 } else {
-                    Err(format!("Undefined variable {}.", name.lexeme))
+                    Err(undefined(name))
                 }
             }
         }
     }
 }
+
+// brief: The error for a name which is not defined in any scope.
+// input:
+// output:
+fn undefined(name: &Token) -> RuntimeError {
+    RuntimeError::new(
+        ErrorCode::UndefinedVariable,
+        format!("Undefined variable {}.", name.lexeme),
+    )
+}
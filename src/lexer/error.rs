@@ -0,0 +1,44 @@
+use std::fmt::Display;
+
+// Stable numbers for the kinds of runtime errors, so that tools can match on them instead of
+// the wording of the messages. The numbers must not change once given out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Other = 0,
+    TypeMismatch = 1,
+    UndefinedVariable = 2,
+    DivisionByZero = 3, // only BigInt division with the bignum feature, 1.0 / 0.0 is inf.
+    ArityMismatch = 4,
+}
+
+impl ErrorCode {
+    pub fn number(self) -> u32 {
+        self as u32
+    }
+}
+
+// A runtime error with its code, which is given where the error occurs, see Interpreter::last_error().
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl RuntimeError {
+    pub fn new(code: ErrorCode, message: String) -> Self {
+        Self { code, message }
+    }
+}
+
+// The errors which are none of the known kinds, like a failed assert.
+impl From<String> for RuntimeError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Other, message)
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "E{:03}: {}", self.code.number(), self.message)
+    }
+}
@@ -1,7 +1,6 @@
-use super::{
-    callable::Callable,
-    token::{LiterialValue, Token, TokenType},
-};
+use std::{cell::RefCell, rc::Rc};
+
+use super::{callable::Callable, environment::Environment, stmt::Stmt, token::Token};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Expr {
@@ -37,6 +36,31 @@ pub enum Expr {
         paren: Token,
         arguments: Vec<Expr>,
     },
+    Block {
+        brace: Token,
+        statements: Vec<Stmt>,
+        value: Option<Box<Expr>>, // the last expression without ";", nil if None.
+    },
+    If {
+        keyword: Token,
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,         // Expr::Block
+        else_branch: Option<Box<Expr>>, // Expr::Block or Expr::If, nil if None.
+    },
+    Lazy {
+        keyword: Token,
+        expression: Box<Expr>, // evaluated when the thunk is forced.
+    },
+    LetIn {
+        name: Token,
+        initializer: Box<Expr>,
+        body: Box<Expr>, // the only place where name is visible.
+    },
+    When {
+        keyword: Token,
+        branches: Vec<(Expr, Expr)>, // ( condition, value ), tried from the top.
+        else_branch: Option<Box<Expr>>, // nil if None.
+    },
 }
 
 impl Expr {
@@ -67,14 +91,109 @@ impl Expr {
             Expr::Variable { name } => name.lexeme.clone(), // Todo: Check.
 
             Expr::Assign { name, value } => {
-                todo!()
+                format!("( {} = {} )", name.lexeme, value.two_string())
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                format!(
+                    "( {} {} {} )",
+                    left.two_string(),
+                    operator.lexeme,
+                    right.two_string()
+                )
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                let arguments_str: Vec<String> = arguments.iter().map(|x| x.two_string()).collect();
+                format!("{}( {} )", callee.two_string(), arguments_str.join(", "))
+            }
+            Expr::Block {
+                statements, value, ..
+            } => {
+                let value_str = match value {
+                    Some(v) => v.two_string(),
+                    None => "nil".to_string(),
+                };
+                if statements.is_empty() {
+                    format!("{{ {} }}", value_str)
+                } else {
+                    format!("{{ ... {} }}", value_str) // statements are not printed.
+                }
+            }
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => match else_branch {
+                Some(v) => format!(
+                    "( if {} {} else {} )",
+                    condition.two_string(),
+                    then_branch.two_string(),
+                    v.two_string()
+                ),
+                None => format!(
+                    "( if {} {} )",
+                    condition.two_string(),
+                    then_branch.two_string()
+                ),
+            },
+            Expr::Lazy { expression, .. } => format!("( lazy {} )", expression.two_string()),
+            Expr::LetIn {
+                name,
+                initializer,
+                body,
+            } => format!(
+                "( let {} = {} in {} )",
+                name.lexeme,
+                initializer.two_string(),
+                body.two_string()
+            ),
+            Expr::When {
+                branches,
+                else_branch,
+                ..
+            } => {
+                let mut branches_str: Vec<String> = branches
+                    .iter()
+                    .map(|(c, v)| format!("{} => {}", c.two_string(), v.two_string()))
+                    .collect();
+                if let Some(v) = else_branch {
+                    branches_str.push(format!("else => {}", v.two_string()));
+                }
+                format!("( when {{ {} }} )", branches_str.join(", "))
             }
-            _ => todo!(),
         }
     }
     pub fn print(&self) {
         println!("{}", self.two_string());
     }
+
+    // brief: Line of the first token with a line in the expression.
+    // input:
+    // output: None for a literal.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Expr::Literal { .. } => None,
+            Expr::Grouping { expression } => expression.line(),
+            Expr::Binary { left, operator, .. } | Expr::Logical { left, operator, .. } => {
+                left.line().or(Some(operator.line_number))
+            }
+            Expr::Call { callee, paren, .. } => callee.line().or(Some(paren.line_number)),
+            Expr::Unary { operator, .. } => Some(operator.line_number),
+            Expr::Variable { name } | Expr::Assign { name, .. } | Expr::LetIn { name, .. } => {
+                Some(name.line_number)
+            }
+            Expr::Block { brace, .. } => Some(brace.line_number),
+            Expr::If { keyword, .. } | Expr::Lazy { keyword, .. } | Expr::When { keyword, .. } => {
+                Some(keyword.line_number)
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -85,6 +204,21 @@ pub enum ExprLiteral {
     False,
     Nil,
     FunctionLiteral(Box<dyn Callable>),
+    Thunk(Thunk),
+    #[cfg(feature = "bignum")]
+    BigIntLiteral(num_bigint::BigInt),
+}
+
+// The value of `lazy expr`, clones share the state so that the expression runs at most once.
+pub type Thunk = Rc<RefCell<ThunkState>>;
+
+#[derive(Clone, Debug)]
+pub enum ThunkState {
+    Pending {
+        expression: Expr,
+        environment: Environment, // the scope where `lazy` was, as it was at that time.
+    },
+    Done(ExprLiteral),
 }
 
 impl PartialEq for ExprLiteral {
@@ -97,6 +231,8 @@ impl PartialEq for ExprLiteral {
             (ExprLiteral::False, ExprLiteral::False) => true,
             (ExprLiteral::Nil, ExprLiteral::Nil) => true,
             (FunctionLiteral(_), FunctionLiteral(_)) => false,
+            #[cfg(feature = "bignum")]
+            (BigIntLiteral(v1), BigIntLiteral(v2)) => *v1 == *v2,
             _ => false,
         }
     }
@@ -111,12 +247,17 @@ impl ExprLiteral {
         match self {
             Self::NumberLiteral(v) => v.to_string(),
             Self::StringLiteral(v) => v.clone(),
-            Self::True => "True".to_string(),
-            Self::False => "False".to_string(),
-            Self::Nil => "Nil".to_string(),
-            Self::FunctionLiteral(f) => {
-                todo!()
-            }
+            // Spelled like the keywords, so that the printed value can be read back.
+            Self::True => "true".to_string(),
+            Self::False => "false".to_string(),
+            Self::Nil => "nil".to_string(),
+            Self::FunctionLiteral(f) => format!("<fn {}>", f.name()),
+            Self::Thunk(t) => match &*t.borrow() {
+                ThunkState::Pending { .. } => "<lazy>".to_string(),
+                ThunkState::Done(v) => v.two_string(),
+            },
+            #[cfg(feature = "bignum")]
+            Self::BigIntLiteral(v) => v.to_string(),
         }
     }
 
@@ -127,6 +268,8 @@ impl ExprLiteral {
             (ExprLiteral::True, ExprLiteral::True) => true,
             (ExprLiteral::False, ExprLiteral::False) => true,
             (ExprLiteral::Nil, ExprLiteral::Nil) => true,
+            #[cfg(feature = "bignum")]
+            (ExprLiteral::BigIntLiteral(v1), ExprLiteral::BigIntLiteral(v2)) => *v1 == *v2,
             _ => false,
         }
     }
@@ -157,15 +300,13 @@ impl ExprLiteral {
 
 #[cfg(test)]
 mod tests {
-    use std::fmt::Binary;
-
     use super::{Expr, ExprLiteral};
-    use super::{LiterialValue, Token, TokenType};
+    use crate::lexer::token::{Token, TokenType};
 
     #[test]
     fn test_expr() {
         let left1 = Box::new(Expr::Unary {
-            operator: Token::new(TokenType::Minus, "-".to_string(), None, 1),
+            operator: Token::new(TokenType::Minus, "-".to_string(), None, 1, 1),
             right: Box::new(Expr::Literal {
                 value: ExprLiteral::NumberLiteral(239.0),
             }),
@@ -178,7 +319,7 @@ mod tests {
 
         let test = Expr::Binary {
             left: left1,
-            operator: Token::new(TokenType::Star, "*".to_string(), None, 1),
+            operator: Token::new(TokenType::Star, "*".to_string(), None, 1, 1),
             right: right1,
         };
 
@@ -0,0 +1,57 @@
+use std::{cell::RefCell, collections::HashMap, fs, rc::Rc};
+
+// Where read_file and write_file go to, so that tests don't touch the disk.
+pub trait FileSystem {
+    fn read(&mut self, path: &str) -> Result<String, String>; // the error is the io message.
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), String>; // overwrite the whole file.
+}
+
+// Read and write the real files.
+#[derive(Debug, Default)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn read(&mut self, path: &str) -> Result<String, String> {
+        fs::read_to_string(path).map_err(|e| e.to_string())
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+}
+
+// Keep the files in memory, clones share the same files so the caller can look at them afterwards.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFileSystem {
+    files: Rc<RefCell<HashMap<String, String>>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(&self, path: &str) -> Option<String> {
+        self.files.borrow().get(path).cloned()
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read(&mut self, path: &str) -> Result<String, String> {
+        match self.files.borrow().get(path) {
+            Some(v) => Ok(v.clone()),
+            None => Err(format!("No such file {}", path)),
+        }
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        if path.is_empty() || path.ends_with('/') {
+            return Err(format!("Not a file path {:?}", path));
+        }
+        self.files
+            .borrow_mut()
+            .insert(path.to_string(), contents.to_string());
+        Ok(())
+    }
+}
@@ -1,26 +1,72 @@
-use crate::lexer::environment::Environment;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::lexer::{environment::Environment, error::RuntimeError};
 
 use super::{
-    callable::Callable, expr::ExprLiteral, interpreter::Interpreter, stmt::Stmt, stmt::Stmt::Block,
-    stmt::Stmt::Function, token::Token,
+    callable::Callable, expr::ExprLiteral, interpreter::Interpreter, stmt::Stmt, token::Token,
 };
 
+// Hashable form of an argument, f64 is kept by its bits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum MemoKey {
+    Number(u64),
+    String(String),
+    True,
+    False,
+    Nil,
+    #[cfg(feature = "bignum")]
+    BigInt(num_bigint::BigInt),
+}
+
+impl MemoKey {
+    // brief: None for values which can't be compared by value, like functions.
+    // input:
+    // output:
+    fn from_literal(value: &ExprLiteral) -> Option<Self> {
+        match value {
+            ExprLiteral::NumberLiteral(v) => Some(Self::Number(v.to_bits())),
+            ExprLiteral::StringLiteral(v) => Some(Self::String(v.clone())),
+            ExprLiteral::True => Some(Self::True),
+            ExprLiteral::False => Some(Self::False),
+            ExprLiteral::Nil => Some(Self::Nil),
+            ExprLiteral::FunctionLiteral(_) | ExprLiteral::Thunk(_) => None,
+            #[cfg(feature = "bignum")]
+            ExprLiteral::BigIntLiteral(v) => Some(Self::BigInt(v.clone())),
+        }
+    }
+}
+
+type MemoCache = Rc<RefCell<HashMap<Vec<MemoKey>, ExprLiteral>>>;
+
 #[derive(Debug, Clone)]
 pub struct MyFunction {
     name: Token,
     params: Vec<Token>,
     body: Vec<Stmt>,
     pub closure: Environment,
+    memo: Option<MemoCache>, // shared by all the clones, including the one used for recursion.
 }
 impl MyFunction {
     pub fn new(declaration: Stmt, closure: Environment) -> Result<Self, String> {
-        if let Stmt::Function { name, params, body } = declaration.clone() {
+        if let Stmt::Function {
+            name,
+            params,
+            body,
+            memo,
+            ..
+        } = declaration.clone()
+        {
             if let Stmt::Block { statements } = *body {
                 return Ok(Self {
                     name,
                     params,
                     body: statements,
                     closure,
+                    memo: if memo {
+                        Some(MemoCache::default())
+                    } else {
+                        None
+                    },
                 });
             }
         }
@@ -38,7 +84,17 @@ impl Callable for MyFunction {
         &mut self,
         interpreter: &mut Interpreter,
         arguments: Vec<ExprLiteral>,
-    ) -> Result<ExprLiteral, String> {
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let key: Option<Vec<MemoKey>> = match &self.memo {
+            Some(_) => arguments.iter().map(MemoKey::from_literal).collect(),
+            None => None,
+        };
+        if let (Some(cache), Some(key)) = (&self.memo, &key) {
+            if let Some(v) = cache.borrow().get(key) {
+                return Ok(v.clone());
+            }
+        }
+
         //let mut environment = Environment::new(Some(Box::new(interpreter.globals.clone())));
         let mut environment = Environment::new(Some(Box::new(self.closure.clone())));
 
@@ -58,12 +114,22 @@ impl Callable for MyFunction {
 
         self.closure = environment;
 
+        if let (Some(cache), Some(key), Ok(v)) = (&self.memo, key, &ans) {
+            cache.borrow_mut().insert(key, v.clone());
+        }
+
         ans
     }
 
+    fn lazy_arguments(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> String {
+        self.name.lexeme.clone()
+    }
+
     fn clone_box(&self) -> Box<dyn Callable> {
         Box::new(self.clone())
     }
-
-    // Todo: add two_string()
 }
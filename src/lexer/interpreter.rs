@@ -1,25 +1,83 @@
-use std::{arch::x86_64::_SIDD_SWORD_OPS, ffi::c_long};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
+#[cfg(feature = "bignum")]
+use super::bignum;
 use super::{
-    callable::MyClock,
+    callable::{
+        Callable, MyAbs, MyAssertApprox, MyClock, MyCurry, MyFormatCurrency, MyNow, MyRandInt,
+        MyRandom, MyReadFile, MySeed, MySleep, MyToString, MyWriteFile,
+    },
+    clock::{Clock, SystemClock},
     environment::Environment,
-    expr::{Expr, ExprLiteral},
+    error::{ErrorCode, RuntimeError},
+    expr::{Expr, ExprLiteral, ThunkState},
+    filesystem::{FileSystem, StdFileSystem},
     function::MyFunction,
-    parser::{self, Parser},
-    stmt::Stmt,
+    output::{Output, StdOutput},
+    random::Rng,
+    stmt::{MatchArm, Pattern, Stmt},
     token::{Token, TokenType},
+    trace::TraceEvent,
 };
 
 pub struct Interpreter {
     environment: Environment, // struct to save variavle and create local scope.
     pub globals: Environment, // global scope.
+    profile: Option<Vec<StatementTime>>, // wall-clock time of each top-level statement.
+    chained_comparisons: bool, // "<" ">" "<=" ">=" return the right operand when true.
+    output: Box<dyn Output>,  // where print and debug write to.
+    filesystem: Box<dyn FileSystem>, // where read_file and write_file go to.
+    rng: Rng,                 // used by random() and randint(), reset by seed().
+    clock: Box<dyn Clock>,    // where clock(), now() and sleep() get the time from.
+    call_stack: Vec<(String, usize)>, // name and calling line of the running functions.
+    max_stack: usize, // bytes of stack calls may use, more is an error instead of an overflow.
+    stack_base: usize, // address near the bottom of the stack, taken when interpreter() starts.
+    lookup_cache: Option<HashMap<String, ExprLiteral>>, // variables already read by the running statement.
+    assert_summary: Option<AssertSummary>, // failed asserts are collected here instead of stopping.
+    last_error: Option<RuntimeError>, // the error of the last interpreter() call, with its code.
+    watchers: HashMap<String, Vec<Box<Watcher>>>, // called on assignment to the variable.
+    trace: Option<Vec<TraceEvent>>,   // what the run did, in order.
+}
+
+// Called with the old and the new value of a watched variable.
+pub type Watcher = dyn FnMut(&ExprLiteral, &ExprLiteral);
+
+// Results of the asserts of one interpreter() call in summary mode.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AssertSummary {
+    pub passed: usize,
+    pub failures: Vec<String>, // the messages of the failed ones, in order.
 }
 
+// Elapsed time of one top-level statement, index counts from 0.
+#[derive(Debug, Clone)]
+pub struct StatementTime {
+    pub index: usize,
+    pub elapsed: Duration,
+}
+
+// Leaves some room in the 2MB stack of a spawned thread. A call takes about 20KB in debug
+// builds, much less in release builds.
+const DEFAULT_MAX_STACK: usize = 1536 * 1024;
+
 pub enum IsReturn {
     Yes(ExprLiteral),
+    Break(String), // leaving the statement with this label.
     No,
 }
 
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Interpreter {
     // brief: Create a Interpreter, with setting previous Env None.
     // input:
@@ -31,62 +89,457 @@ impl Interpreter {
             "clock".to_string(),
             ExprLiteral::FunctionLiteral(Box::new(MyClock)),
         );
+        globals.define(
+            "abs".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyAbs)),
+        );
+        globals.define(
+            "to_string".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyToString)),
+        );
+        globals.define(
+            "curry".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyCurry)),
+        );
+        globals.define(
+            "read_file".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyReadFile)),
+        );
+        globals.define(
+            "write_file".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyWriteFile)),
+        );
+        globals.define(
+            "seed".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MySeed)),
+        );
+        globals.define(
+            "random".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyRandom)),
+        );
+        globals.define(
+            "randint".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyRandInt)),
+        );
+        globals.define(
+            "assert_approx".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyAssertApprox)),
+        );
+        globals.define(
+            "format_currency".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyFormatCurrency)),
+        );
+        globals.define(
+            "sleep".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MySleep)),
+        );
+        globals.define(
+            "now".to_string(),
+            ExprLiteral::FunctionLiteral(Box::new(MyNow)),
+        );
+
+        // Different every run unless the script calls seed(), or with_seed() is used.
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
 
         Self {
             environment: globals.clone(),
             globals,
+            profile: None,
+            chained_comparisons: false,
+            output: Box::new(StdOutput),
+            filesystem: Box::new(StdFileSystem),
+            rng: Rng::new(now),
+            clock: Box::new(SystemClock),
+            call_stack: vec![],
+            max_stack: DEFAULT_MAX_STACK,
+            stack_base: 0,
+            lookup_cache: Some(HashMap::new()),
+            assert_summary: None,
+            last_error: None,
+            watchers: HashMap::new(),
+            trace: None,
+        }
+    }
+
+    // brief: Replace stdout / stderr, e.g. by a CaptureOutput in tests.
+    // input:
+    // output:
+    pub fn with_output(mut self, output: Box<dyn Output>) -> Self {
+        self.output = output;
+        self
+    }
+
+    // brief: Replace the real files, e.g. by a MemoryFileSystem in tests.
+    // input:
+    // output:
+    pub fn with_filesystem(mut self, filesystem: Box<dyn FileSystem>) -> Self {
+        self.filesystem = filesystem;
+        self
+    }
+
+    // brief: Used by the natives to reach the files.
+    // input:
+    // output:
+    pub fn filesystem(&mut self) -> &mut dyn FileSystem {
+        self.filesystem.as_mut()
+    }
+
+    // brief: Start random() and randint() from a fixed seed, the same as seed(n) in the script.
+    // input:
+    // output:
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    // brief: Used by the natives to draw random numbers or reseed.
+    // input:
+    // output:
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    // brief: Replace the real time, e.g. by a FakeClock in tests so that sleep() doesn't wait.
+    // input:
+    // output:
+    pub fn with_clock(mut self, clock: Box<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // brief: Used by the natives to read the time or sleep.
+    // input:
+    // output:
+    pub fn clock(&mut self) -> &mut dyn Clock {
+        self.clock.as_mut()
+    }
+
+    // brief: How many bytes of stack nested calls may use, 1.5MB by default. Raise it when
+    //        running on a bigger stack, like the 8MB main thread.
+    // input:
+    // output:
+    pub fn with_max_stack(mut self, bytes: usize) -> Self {
+        self.max_stack = bytes;
+        self
+    }
+
+    // brief: Make a native function of the host visible to scripts under its name().
+    // input:
+    // output:
+    pub fn with_native(mut self, function: Box<dyn Callable>) -> Self {
+        let name = function.name();
+        let value = ExprLiteral::FunctionLiteral(function);
+        self.globals.define(name.clone(), value.clone());
+        self.environment.define(name, value);
+        self
+    }
+
+    // brief: Line of the running call, so that a native can say where its error comes from.
+    // input:
+    // output: 0 when nothing is being called.
+    pub fn call_line(&self) -> usize {
+        self.call_stack.last().map_or(0, |(_, line)| *line)
+    }
+
+    // brief: In this mode a true comparison returns its right operand and a false one returns false,
+    //        so that 0.0 < x < 10.0 means ( 0.0 < x ) and ( x < 10.0 ). When the left comparison of
+    //        a chain is false, the rest is not evaluated. "==" and "!=" are not changed.
+    // input:
+    // output:
+    pub fn with_chained_comparisons(mut self, chained: bool) -> Self {
+        self.chained_comparisons = chained;
+        self
+    }
+
+    // brief: Remember the variables read by a statement, so that reading them again in the same
+    //        statement doesn't walk the scope chain. It's on by default, assignment updates it and
+    //        it's dropped when the statement ends or a function is called.
+    // input:
+    // output:
+    pub fn with_lookup_cache(mut self, cache: bool) -> Self {
+        self.lookup_cache = if cache { Some(HashMap::new()) } else { None };
+        self
+    }
+
+    // brief: Record how long every top-level statement takes, see profile_report().
+    // input:
+    // output:
+    pub fn with_profile(mut self, profile: bool) -> Self {
+        self.profile = if profile { Some(vec![]) } else { None };
+        self
+    }
+
+    // brief: Timings of the last interpreter() call, None if profiling is off.
+    // input:
+    // output:
+    pub fn profile_report(&self) -> Option<&Vec<StatementTime>> {
+        self.profile.as_ref()
+    }
+
+    // brief: Run a script full of asserts as a test file: a failed assert (or assert_approx) is
+    //        collected and the script goes on. "N passed, M failed" and the failures are printed
+    //        at the end, and interpreter() gives an error if any failed.
+    // input:
+    // output:
+    pub fn with_assert_summary(mut self, summary: bool) -> Self {
+        self.assert_summary = if summary {
+            Some(AssertSummary::default())
+        } else {
+            None
+        };
+        self
+    }
+
+    // brief: Asserts of the last interpreter() call, None if the summary mode is off.
+    // input:
+    // output:
+    pub fn assert_summary(&self) -> Option<&AssertSummary> {
+        self.assert_summary.as_ref()
+    }
+
+    // brief: Count an assert, a failed one is an error unless the summary mode is on.
+    // input: failure: the message of a failed assert, None if it passed.
+    // output:
+    pub fn report_assertion(&mut self, failure: Option<String>) -> Result<(), RuntimeError> {
+        match (&mut self.assert_summary, failure) {
+            (Some(summary), None) => summary.passed += 1,
+            (Some(summary), Some(message)) => summary.failures.push(message),
+            (None, Some(message)) => return Err(message.into()),
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    // brief: Record every statement, let, assignment, print and call of the run, see trace().
+    // input:
+    // output:
+    pub fn with_trace(mut self, trace: bool) -> Self {
+        self.trace = if trace { Some(vec![]) } else { None };
+        self
+    }
+
+    // brief: Events of the last interpreter() call, None if tracing is off.
+    // input:
+    // output:
+    pub fn trace(&self) -> Option<&Vec<TraceEvent>> {
+        self.trace.as_ref()
+    }
+
+    // brief: Add an event to the trace, the closure is only called when tracing is on.
+    // input:
+    // output:
+    fn record_event(&mut self, event: impl FnOnce() -> TraceEvent) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(event());
         }
     }
 
     // brief: Pub function to evaluate Vec<Stmt> by Match all kinds of Stmt.
+    //        A panic, e.g. from a native of the host, is returned as an error as well, so that the
+    //        host keeps running. The Interpreter shouldn't be used again after that.
     // input:
     // output:
     pub fn interpreter(&mut self, statements: &Vec<Stmt>) -> Result<ExprLiteral, String> {
-        for statement in statements {
-            if let IsReturn::Yes(val) = self.execute(statement)? {
-                return Ok(val);
+        let base = 0u8;
+        self.stack_base = &base as *const u8 as usize;
+        if let Some(summary) = &mut self.assert_summary {
+            *summary = AssertSummary::default();
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+        let result = match panic::catch_unwind(AssertUnwindSafe(|| self.run(statements))) {
+            Ok(result) => result,
+            Err(payload) => {
+                let message = if let Some(v) = payload.downcast_ref::<&str>() {
+                    v.to_string()
+                } else if let Some(v) = payload.downcast_ref::<String>() {
+                    v.clone()
+                } else {
+                    "unknown panic".to_string()
+                };
+                Err(format!("Internal error occur when interpreter: {}.", message).into())
+            }
+        };
+        let result = self.finish_assert_summary(result);
+        self.last_error = result.as_ref().err().cloned();
+        result.map_err(|e| e.message)
+    }
+
+    // brief: Call callback with the old and the new value whenever a variable of this name is
+    //        assigned, in any scope. `let` and the parameters of a call are not assignments.
+    // input:
+    // output:
+    pub fn watch(
+        &mut self,
+        name: &str,
+        callback: impl FnMut(&ExprLiteral, &ExprLiteral) + 'static,
+    ) {
+        self.watchers
+            .entry(name.to_string())
+            .or_default()
+            .push(Box::new(callback));
+    }
+
+    fn notify_watchers(&mut self, name: &Token, old_value: &ExprLiteral, new_value: &ExprLiteral) {
+        if let Some(watchers) = self.watchers.get_mut(&name.lexeme) {
+            for watcher in watchers {
+                watcher(old_value, new_value);
+            }
+        }
+    }
+
+    // brief: The error of the last interpreter() call with its ErrorCode, None if it went well.
+    // input:
+    // output:
+    pub fn last_error(&self) -> Option<&RuntimeError> {
+        self.last_error.as_ref()
+    }
+
+    // brief: Print the summary of the asserts at the end of a run in the summary mode.
+    // input:
+    // output: An error if any assert failed, an error of the run itself goes first.
+    fn finish_assert_summary(
+        &mut self,
+        result: Result<ExprLiteral, RuntimeError>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let summary = match &self.assert_summary {
+            Some(v) => v.clone(),
+            None => return result,
+        };
+        let mut text = format!(
+            "{} passed, {} failed\n",
+            summary.passed,
+            summary.failures.len()
+        );
+        for failure in &summary.failures {
+            text.push_str(&format!("  {}\n", failure));
+        }
+        self.output.print(&text);
+
+        if result.is_ok() && !summary.failures.is_empty() {
+            return Err(format!(
+                "{} of {} assertions failed.",
+                summary.failures.len(),
+                summary.passed + summary.failures.len()
+            )
+            .into());
+        }
+        result
+    }
+
+    fn run(&mut self, statements: &Vec<Stmt>) -> Result<ExprLiteral, RuntimeError> {
+        if self.profile.is_none() {
+            return Self::returned_value(self.execute_statements(statements)?);
+        }
+        self.profile = Some(vec![]);
+
+        for (index, statement) in statements.iter().enumerate() {
+            let start = Instant::now();
+            let result = self.execute(statement);
+            if let Some(profile) = &mut self.profile {
+                profile.push(StatementTime {
+                    index,
+                    elapsed: start.elapsed(),
+                });
+            }
+            match result? {
+                IsReturn::No => {}
+                jump => return Self::returned_value(jump),
             }
         }
         Ok(ExprLiteral::Nil)
     }
 
-    fn check_return(&self, result: IsReturn) -> Result<IsReturn, String> {
-        if let IsReturn::Yes(val) = result {
-            return Ok(IsReturn::Yes(val));
+    // brief: Evaluate Vec<Stmt> of a block or a function body.
+    // input:
+    // output:
+    fn execute_statements(&mut self, statements: &Vec<Stmt>) -> Result<IsReturn, RuntimeError> {
+        for statement in statements {
+            match self.execute(statement)? {
+                IsReturn::No => {}
+                jump => return Ok(jump),
+            }
         }
         Ok(IsReturn::No)
     }
 
-    fn execute(&mut self, statement: &Stmt) -> Result<IsReturn, String> {
+    // brief: Value of a script or a function body, a break can't get out of them.
+    //        The parser already rejects that, so it's only checked for safety.
+    // input:
+    // output:
+    fn returned_value(result: IsReturn) -> Result<ExprLiteral, RuntimeError> {
+        match result {
+            IsReturn::Yes(val) => Ok(val),
+            IsReturn::No => Ok(ExprLiteral::Nil),
+            IsReturn::Break(label) => Err(format!(
+                "Error occur when interpreter at break {} for leaving a function or the script.",
+                label
+            )
+            .into()),
+        }
+    }
+
+    // brief: Execute one statement, the lookup cache only lives as long as it, since a nested
+    //        statement like a block may change which variable a name refers to.
+    // input:
+    // output:
+    fn execute(&mut self, statement: &Stmt) -> Result<IsReturn, RuntimeError> {
+        self.record_event(|| TraceEvent::Statement {
+            line: statement.line(),
+        });
+        self.clear_lookup_cache();
+        let result = self.execute_statement(statement);
+        self.clear_lookup_cache();
+        result
+    }
+
+    fn clear_lookup_cache(&mut self) {
+        if let Some(cache) = &mut self.lookup_cache {
+            cache.clear();
+        }
+    }
+
+    fn execute_statement(&mut self, statement: &Stmt) -> Result<IsReturn, RuntimeError> {
         match statement {
             // If just an expression.
             Stmt::Expression(v) => {
                 let _ = self.evaluate(v)?; // Evaluate Expression.
             }
             // If a print statement.
-            Stmt::Print(v) => {
-                println!("{}", (self.evaluate(v)?).two_string()); // Print Expression.
-            }
+            Stmt::Print {
+                keyword,
+                values,
+                sep,
+                end,
+            } => self.execute_print(keyword, values, sep, end)?,
             // If a Var defination.
-            Stmt::Let { name, initializer } => {
+            Stmt::Let {
+                name, initializer, ..
+            } => {
                 let value;
                 if *initializer
                     != (Expr::Literal {
                         value: ExprLiteral::Nil,
                     })
                 {
-                    value = self.evaluate(initializer)?;
+                    value = self.evaluate_lazily(initializer)?;
+                    self.record_event(|| TraceEvent::Define {
+                        line: name.line_number,
+                        name: name.lexeme.clone(),
+                        value: value.two_string(),
+                    });
                     self.environment.define(name.lexeme.clone(), value); // Define variable in the temp Environment.
                 }
             }
             // If a Block.
             Stmt::Block { statements } => {
                 self.environment = Environment::new(Some(Box::new(self.environment.clone()))); // Save temp environment.and Restore later.
-                let block_return = self.interpreter(statements)?; // Scope recursively; // return Todo
-                self.environment = *self.environment.enclosing.clone().unwrap();
-                if block_return != ExprLiteral::Nil {
-                    return Ok(IsReturn::Yes(block_return));
-                }
+                let block_return = self.execute_statements(statements); // Scope recursively.
+                self.leave_scope();
+                return block_return; // a return or a break goes on to the enclosing statement.
             }
             // If an If.
             Stmt::If {
@@ -110,22 +563,22 @@ impl Interpreter {
             Stmt::While { condition, body } => {
                 let mut while_condition = self.evaluate(condition)?;
                 while self.is_truthy(&while_condition) == ExprLiteral::True {
-                    // self.execute(body)?;
-                    if let IsReturn::Yes(val) = self.execute(body)? {
-                        return Ok(IsReturn::Yes(val));
+                    match self.execute(body)? {
+                        IsReturn::No => {}
+                        jump => return Ok(jump),
                     }
                     while_condition = self.evaluate(condition)?;
                 }
             }
             // If a Function statement.
-            Stmt::Function { name, params, body } => {
+            Stmt::Function { name, .. } => {
                 let function = MyFunction::new(statement.clone(), self.environment.clone())?; // closure don't include function-itself
                 self.environment.define(
                     name.lexeme.clone(),
                     ExprLiteral::FunctionLiteral(Box::new(function.clone())),
                 );
             }
-            Stmt::Return { keyword, value } => {
+            Stmt::Return { value, .. } => {
                 let return_value = if *value
                     == (Expr::Literal {
                         value: ExprLiteral::Nil,
@@ -138,10 +591,250 @@ impl Interpreter {
 
                 return Ok(IsReturn::Yes(return_value));
             }
+            // If an Assert.
+            Stmt::Assert { keyword, condition } => self.execute_assert(keyword, condition)?,
+            // If a Labeled statement, a break to its label stops here.
+            Stmt::Labeled { label, body } => {
+                return match self.execute(body)? {
+                    IsReturn::Break(target) if target == label.lexeme => Ok(IsReturn::No),
+                    jump => Ok(jump),
+                };
+            }
+            Stmt::Break { label, .. } => return Ok(IsReturn::Break(label.lexeme.clone())),
+            // If a Repeat.
+            Stmt::Repeat {
+                keyword,
+                count,
+                body,
+            } => return self.execute_repeat(keyword, count, body),
+            // If an Unset.
+            Stmt::Unset { name, .. } => self.environment.undefine(name)?,
+            // If a Match.
+            Stmt::Match { value, arms, .. } => return self.execute_match(value, arms),
+            // If a Debug, go on after it.
+            Stmt::Debug { keyword } => self.execute_debug(keyword),
+        }
+        Ok(IsReturn::No)
+    }
+
+    // The statements below are kept out of execute_statement(), so that its stack frame stays small.
+
+    // brief: Print the values joined by sep and followed by end.
+    // input:
+    // output:
+    fn execute_print(
+        &mut self,
+        keyword: &Token,
+        values: &Vec<Expr>,
+        sep: &Option<Expr>,
+        end: &Option<Expr>,
+    ) -> Result<(), RuntimeError> {
+        let mut printed = vec![];
+        for v in values {
+            printed.push(self.evaluate(v)?.two_string());
+        }
+        let sep = match sep {
+            Some(v) => self.evaluate(v)?.two_string(),
+            None => " ".to_string(),
+        };
+        let end = match end {
+            Some(v) => self.evaluate(v)?.two_string(),
+            None => "\n".to_string(),
+        };
+        let text = format!("{}{}", printed.join(&sep), end);
+        self.record_event(|| TraceEvent::Print {
+            line: keyword.line_number,
+            text: text.clone(),
+        });
+        self.output.print(&text); // Print Expression.
+        Ok(())
+    }
+
+    // brief: Fail with the values of the sub-expressions when the condition is false.
+    // input:
+    // output:
+    fn execute_assert(&mut self, keyword: &Token, condition: &Expr) -> Result<(), RuntimeError> {
+        let mut recorded = vec![];
+        let value = self.evaluate_recorded(condition, &mut recorded)?;
+        let mut failure = None;
+        if self.is_truthy(&value) == ExprLiteral::False {
+            let mut message = format!(
+                "Assertion failed at line {}: {}",
+                keyword.line_number,
+                condition.two_string()
+            );
+            if !recorded.is_empty() {
+                message.push_str(", where ");
+                message.push_str(&recorded.join(", "));
+            }
+            message.push('.');
+            failure = Some(message);
+        }
+        self.report_assertion(failure)
+    }
+
+    // brief: Run body count times, a break or return stops it early.
+    // input:
+    // output:
+    fn execute_repeat(
+        &mut self,
+        keyword: &Token,
+        count: &Expr,
+        body: &Stmt,
+    ) -> Result<IsReturn, RuntimeError> {
+        let times = match self.evaluate(count)? {
+            ExprLiteral::NumberLiteral(v) if v >= 0.0 && v.fract() == 0.0 => v,
+            other => {
+                return Err(format!(
+                    "Error occur when interpreter at line {} at {} for a count which is not a whole number >= 0, got {}.",
+                    keyword.line_number,
+                    keyword.lexeme,
+                    other.two_string()
+                ).into())
+            }
+        };
+        let mut done = 0.0;
+        while done < times {
+            match self.execute(body)? {
+                IsReturn::No => {}
+                jump => return Ok(jump),
+            }
+            done += 1.0;
         }
         Ok(IsReturn::No)
     }
 
+    // brief: Run the first arm whose pattern fits, in a new scope for the binding.
+    // input:
+    // output:
+    fn execute_match(
+        &mut self,
+        value: &Expr,
+        arms: &Vec<MatchArm>,
+    ) -> Result<IsReturn, RuntimeError> {
+        let value = self.evaluate(value)?;
+        for arm in arms {
+            if !Self::pattern_matches(&arm.pattern, &value) {
+                continue;
+            }
+            self.environment = Environment::new(Some(Box::new(self.environment.clone())));
+            if let Pattern::Type {
+                binding: Some(name),
+                ..
+            }
+            | Pattern::Binding(name) = &arm.pattern
+            {
+                self.environment.define(name.lexeme.clone(), value.clone());
+            }
+            if let Some(guard) = &arm.guard {
+                self.clear_lookup_cache(); // the binding may shadow an outer variable.
+                let passed = self.evaluate(guard).map(|v| self.is_truthy(&v));
+                if passed != Ok(ExprLiteral::True) {
+                    self.leave_scope();
+                    self.clear_lookup_cache();
+                    passed?;
+                    continue; // try the next arm.
+                }
+            }
+            let result = self.execute(&arm.body);
+            self.leave_scope();
+            return result;
+        }
+        Ok(IsReturn::No) // no arm fits, like an if without else.
+    }
+
+    // brief: Dump the variables and the call stack to the error output.
+    // input:
+    // output:
+    fn execute_debug(&mut self, keyword: &Token) {
+        let mut text = format!("[debug] line {}\n", keyword.line_number);
+        for (name, value) in self.environment.variables() {
+            text.push_str(&format!("[debug]   {} = {}\n", name, value.two_string()));
+        }
+        text.push_str("[debug] call stack: <script>");
+        for (name, line) in &self.call_stack {
+            text.push_str(&format!(" -> {} (line {})", name, line));
+        }
+        text.push('\n');
+        self.output.eprint(&text);
+    }
+
+    // brief: Evaluate like match_expr(), but also write "source = value" of the sub-expressions into
+    //        recorded, so that a failed assert can show them. Sub-expressions are evaluated only once,
+    //        their values are fed back to match_expr() as literals.
+    // input:
+    // output:
+    fn evaluate_recorded(
+        &mut self,
+        expr: &Expr,
+        recorded: &mut Vec<String>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        match expr {
+            Expr::Grouping { expression } => self.evaluate_recorded(expression, recorded),
+            Expr::Unary { operator, right } => {
+                let right = self.record(right, recorded)?;
+                self.match_expr(&Expr::Unary {
+                    operator: operator.clone(),
+                    right: Box::new(right),
+                })
+            }
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } if !(self.chained_comparisons && is_comparison(operator)) => {
+                let left = self.record(left, recorded)?;
+                let right = self.record(right, recorded)?;
+                self.match_expr(&Expr::Binary {
+                    left: Box::new(left),
+                    operator: operator.clone(),
+                    right: Box::new(right),
+                })
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.record(left, recorded)?;
+                self.match_expr(&Expr::Logical {
+                    left: Box::new(left),
+                    operator: operator.clone(),
+                    right: right.clone(), // Only evaluated when not short-circuited.
+                })
+            }
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => {
+                let callee = self.evaluate(callee)?; // first, like call_expr().
+                let mut literal_arguments = vec![];
+                for argument in arguments {
+                    literal_arguments.push(self.record(argument, recorded)?);
+                }
+                self.match_expr(&Expr::Call {
+                    callee: Box::new(Expr::Literal { value: callee }),
+                    paren: paren.clone(),
+                    arguments: literal_arguments,
+                })
+            }
+            _ => self.evaluate(expr),
+        }
+    }
+
+    // brief: Evaluate a sub-expression of an assert, and record it unless it's a literal.
+    // input:
+    // output: The value wrapped as Expr::Literal.
+    fn record(&mut self, expr: &Expr, recorded: &mut Vec<String>) -> Result<Expr, RuntimeError> {
+        let value = self.evaluate_recorded(expr, recorded)?;
+        match (expr, &value) {
+            (Expr::Literal { .. }, _) | (_, ExprLiteral::FunctionLiteral(_)) => {}
+            _ => recorded.push(format!("{} = {}", expr.two_string(), value.two_string())),
+        }
+        Ok(Expr::Literal { value })
+    }
+
     // brief: Interperter a function block , and refresh the Global environemnt.
     // input:
     // output:
@@ -149,33 +842,63 @@ impl Interpreter {
         &mut self,
         statements: &Vec<Stmt>,
         environemnt: &mut Environment,
-    ) -> Result<ExprLiteral, String> {
+    ) -> Result<ExprLiteral, RuntimeError> {
         let previous = self.environment.clone(); // save origin environment.
 
         self.environment = environemnt.clone(); // create a function call temporary environment by clone the function's closure.
 
-        let return_value = self.interpreter(statements)?; // interpreter in the temp environment.
+        let return_value = self.execute_statements(statements); // interpreter in the temp environment.
 
         // dbg!("{:?}", self.environment.enclosing.clone());
 
         environemnt.enclosing = self.environment.enclosing.clone(); // restore the changes back to closure.
 
-        self.environment = previous; // restore the origin environment.
+        self.environment = previous; // restore the origin environment, also when an error occurs.
+
+        Self::returned_value(return_value?)
+    }
+
+    // brief: Bytes of stack between here and interpreter(), the stack grows down.
+    // input:
+    // output:
+    fn stack_used(&self) -> usize {
+        let here = 0u8;
+        self.stack_base
+            .saturating_sub(std::hint::black_box(&here) as *const u8 as usize)
+    }
+
+    // brief: Go back to the enclosing scope of a block, the changes of outer variables are kept.
+    // input:
+    // output:
+    fn leave_scope(&mut self) {
+        if let Some(enclosing) = self.environment.enclosing.take() {
+            self.environment = *enclosing;
+        }
+    }
 
-        Ok(return_value)
+    // brief: Evaluate an Expression, a `lazy` one is forced, so the value can be used right away.
+    // input:
+    // output:
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<ExprLiteral, RuntimeError> {
+        let value = self.match_expr(expr)?;
+        self.force(value)
     }
 
-    // brief: Evaluate an Expression.
+    // brief: Evaluate like evaluate(), but `lazy expr` gives its thunk unforced. Only for the values
+    //        bound to a name: let, assignment, let-in and the arguments of script functions.
     // input:
     // output:
-    pub fn evaluate(&mut self, expr: &Expr) -> Result<ExprLiteral, String> {
-        self.match_expr(expr)
+    fn evaluate_lazily(&mut self, expr: &Expr) -> Result<ExprLiteral, RuntimeError> {
+        match expr {
+            Expr::Grouping { expression } => self.evaluate_lazily(expression),
+            _ => self.match_expr(expr),
+        }
     }
 
     // brief: Match all kinds of Expression recursively.
     // input:
     // output:
-    fn match_expr(&mut self, expr: &Expr) -> Result<ExprLiteral, String> {
+    fn match_expr(&mut self, expr: &Expr) -> Result<ExprLiteral, RuntimeError> {
         match expr {
             // 1 Literal
             Expr::Literal { value } => Ok(value.clone()),
@@ -184,60 +907,39 @@ impl Interpreter {
             Expr::Grouping { expression } => self.evaluate(expression), // recursively.
 
             // 3 Unary
-            Expr::Unary { operator, right } => {
-                if operator.token_type == TokenType::Minus {
-                    if let ExprLiteral::NumberLiteral(v) = self.evaluate(right)? {
-                        return Ok(ExprLiteral::NumberLiteral(-v));
-                    }
-                    return Err(format!(
-                        "Error occur when interpreter number at line {} at {}.",
-                        operator.line_number, operator.lexeme
-                    ));
-                } else if operator.token_type == TokenType::Bang {
-                    let evaluated = self.evaluate(right)?;
-                    return Ok(self.is_truthy(&evaluated));
-                }
-                Err(format!(
-                    "Error occur when interpreter at line {} at {} for no matching unary operator.",
-                    operator.line_number, operator.lexeme
-                ))
-            }
+            Expr::Unary { operator, right } => self.unary_expr(operator, right),
             // 8 Call
             Expr::Call {
                 callee,
                 paren,
                 arguments,
-            } => {
-                let callee = self.evaluate(callee)?;
-
-                let arguments: Result<Vec<ExprLiteral>, String> =
-                    arguments.iter().map(|x| self.evaluate(x)).collect();
-
-                if let ExprLiteral::FunctionLiteral(mut f) = callee {
-                    let args = arguments?;
-                    if args.len() != f.arity() {
-                        return Err(format!(
-                            "Error occur , function expect {} args, but got {} at line: {}.",
-                            f.arity(),
-                            args.len(),
-                            paren.line_number
-                        ));
-                    }
-                    return f.call(self, args);
-                }
-                Err(format!(
-                    "Error occur when interpreter a function at line : {} at {}. Expected FunctionLiteral, got others.",
-                    paren.line_number, paren.lexeme
-                ))
-            }
+            } => self.call_expr(callee, paren, arguments),
 
             // 4 Variable
-            Expr::Variable { name } => Ok(self.environment.get(name)?), // Get variable.
+            Expr::Variable { name } => {
+                if let Some(v) = self.lookup_cache.as_ref().and_then(|c| c.get(&name.lexeme)) {
+                    return Ok(v.clone());
+                }
+                let value = self.force(self.environment.get(name)?)?; // Get variable.
+                if let Some(cache) = &mut self.lookup_cache {
+                    cache.insert(name.lexeme.clone(), value.clone());
+                }
+                Ok(value)
+            }
 
             // 6 Assign
             Expr::Assign { name, value } => {
-                let new_value = self.evaluate(value)?; // recursively.
-                self.environment.assign(name, new_value.clone())?; // define variable.
+                let new_value = self.evaluate_lazily(value)?; // recursively.
+                let old_value = self.environment.assign(name, new_value.clone())?; // define variable.
+                if let Some(cache) = &mut self.lookup_cache {
+                    cache.insert(name.lexeme.clone(), new_value.clone()); // the same name, the same variable.
+                }
+                self.record_event(|| TraceEvent::Assign {
+                    line: name.line_number,
+                    name: name.lexeme.clone(),
+                    value: new_value.two_string(),
+                });
+                self.notify_watchers(name, &old_value, &new_value);
                 Ok(new_value)
             }
             // 7 Logical
@@ -260,152 +962,483 @@ impl Interpreter {
                 }
             }
 
+            // 9 Block, in a new scope like Stmt::Block.
+            Expr::Block {
+                brace,
+                statements,
+                value,
+            } => {
+                self.environment = Environment::new(Some(Box::new(self.environment.clone())));
+                self.clear_lookup_cache();
+                let result = self.block_value(brace, statements, value);
+                self.leave_scope();
+                self.clear_lookup_cache(); // names read in the block may be shadowed ones.
+                result
+            }
+
+            // 10 If
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let if_condition = self.evaluate(condition)?;
+                if self.is_truthy(&if_condition) == ExprLiteral::True {
+                    self.evaluate(then_branch)
+                } else if let Some(v) = else_branch {
+                    self.evaluate(v)
+                } else {
+                    Ok(ExprLiteral::Nil)
+                }
+            }
+
+            // 11 Lazy
+            Expr::Lazy { expression, .. } => Ok(ExprLiteral::Thunk(Rc::new(RefCell::new(
+                ThunkState::Pending {
+                    expression: *expression.clone(),
+                    environment: self.environment.clone(),
+                },
+            )))),
+
+            // 12 LetIn
+            Expr::LetIn {
+                name,
+                initializer,
+                body,
+            } => self.let_in_value(name, initializer, body),
+
+            // 13 When
+            Expr::When {
+                branches,
+                else_branch,
+                ..
+            } => self.when_value(branches, else_branch),
+
             // 5 Binary
             Expr::Binary {
                 left,
                 operator,
                 right,
-            } => {
-                let left_operand = self.evaluate(left)?; // recursively.
-                let right_operand = self.evaluate(right)?; // recursively.
-
-                match operator.token_type {
-                    TokenType::Minus => {
-                        if let (true, l_number, r_number) =
-                            self.check_number_operands(&left_operand, &right_operand)
-                        {
-                            return Ok(ExprLiteral::NumberLiteral(l_number - r_number));
-                        }
-                        Err(format!(
-                            "Error occur when interpreter at line {} at {} for some wrong operand.",
-                            operator.line_number, operator.lexeme
-                        ))
-                    },
-                    TokenType::Slash => {
-                        if let (true, l_number, r_number) =
-                            self.check_number_operands(&left_operand, &right_operand)
-                        {
-                            return Ok(ExprLiteral::NumberLiteral(l_number / r_number));
-                        }
-                        Err(format!(
-                            "Error occur when interpreter at line {} at {} for some wrong operand.",
-                            operator.line_number, operator.lexeme
-                        ))
-                    },
-                    TokenType::Star => {
-                        if let (true, l_number, r_number) =
-                            self.check_number_operands(&left_operand, &right_operand)
-                        {
-                            return Ok(ExprLiteral::NumberLiteral(l_number * r_number));
-                        }
-                        Err(format!(
-                            "Error occur when interpreter at line {} at {} for some wrong operand.",
-                            operator.line_number, operator.lexeme
-                        ))
-                    },
-                    TokenType::Plus => match (left_operand, right_operand) {
-                        (
-                            ExprLiteral::NumberLiteral(l_number),
-                            ExprLiteral::NumberLiteral(r_number),
-                        ) => Ok(ExprLiteral::NumberLiteral(l_number + r_number)),
-
-                        (
-                            ExprLiteral::StringLiteral(l_string),
-                            ExprLiteral::StringLiteral(r_string),
-                        ) => Ok(ExprLiteral::StringLiteral(format!(
-                            "{}{}",
-                            l_string, r_string
-                        ))),
-
-                        _ => {
-                            Err(format!(
-                            "Error occur when interpreter at line {} at {} for some wrong operand.",
-                            operator.line_number, operator.lexeme
-                        ))
-                        }
-                    },
-                    TokenType::Greater => {
-                        if let (true, l_number, r_number) =
-                            self.check_number_operands(&left_operand, &right_operand)
-                        {
-                            if l_number > r_number {
-                                return Ok(ExprLiteral::True);
-                            } else {
-                                return Ok(ExprLiteral::False);
-                            }
-                        }
-                         Err(format!(
-                            "Error occur when interpreter at line {} at {} for some wrong operand.",
-                            operator.line_number, operator.lexeme
-                        ))
-                    },
-                    TokenType::GreaterEqual => {
-                        if let (true, l_number, r_number) =
-                            self.check_number_operands(&left_operand, &right_operand)
-                        {
-                            if l_number >= r_number {
-                                return Ok(ExprLiteral::True);
-                            } else {
-                                return Ok(ExprLiteral::False);
-                            }
-                        }
-                         Err(format!(
-                            "Error occur when interpreter at line {} at {} for some wrong operand.",
-                            operator.line_number, operator.lexeme
-                        ))
-                    },
-                    TokenType::Less => {
-                        if let (true, l_number, r_number) =
-                            self.check_number_operands(&left_operand, &right_operand)
-                        {
-                            if l_number < r_number {
-                                return Ok(ExprLiteral::True);
-                            } else {
-                                return Ok(ExprLiteral::False);
-                            }
-                        }
-                         Err(format!(
-                            "Error occur when interpreter at line {} at {} for some wrong operand.",
-                            operator.line_number, operator.lexeme
-                        ))
-                    },
-                    TokenType::LessEqual => {
-                        if let (true, l_number, r_number) =
-                            self.check_number_operands(&left_operand, &right_operand)
-                        {
-                            if l_number <= r_number {
-                                return Ok(ExprLiteral::True);
-                            } else {
-                                return Ok(ExprLiteral::False);
-                            }
-                        }
-                         Err(format!(
-                            "Error occur when interpreter at line {} at {} for some wrong operand.",
-                            operator.line_number, operator.lexeme
-                        ))
-                    },
-                    TokenType::EqualEqual => {
-                        if left_operand == right_operand {
-                            Ok(ExprLiteral::True)
-                        } else {
-                            Ok(ExprLiteral::False)
-                        }
-                    },
-                    TokenType::BangEqual => {
-                        if left_operand  != right_operand {
-                            Ok(ExprLiteral::True)
-                        } else {
-                            Ok(ExprLiteral::False)
-                        }
-                    },
-                    _ => {
-                         Err(format!(
-                            "Error occur when interpreter at line {} at {} for no matchine Binary operator.",
-                            operator.line_number, operator.lexeme
-                        ))
+            } => self.binary_expr(left, operator, right),
+        }
+    }
+
+    // The arms below are kept out of match_expr(), so that its stack frame stays small, every
+    // nesting level of an expression takes one.
+
+    // brief: ( "!" | "-" ) right.
+    // input:
+    // output:
+    fn unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<ExprLiteral, RuntimeError> {
+        if operator.token_type == TokenType::Minus {
+            match self.evaluate(right)? {
+                ExprLiteral::NumberLiteral(v) => return Ok(ExprLiteral::NumberLiteral(-v)),
+                #[cfg(feature = "bignum")]
+                ExprLiteral::BigIntLiteral(v) => return Ok(ExprLiteral::BigIntLiteral(-v)),
+                _ => {}
+            }
+            return Err(RuntimeError::new(
+                ErrorCode::TypeMismatch,
+                format!(
+                    "Error occur when interpreter number at line {} at {}.",
+                    operator.line_number, operator.lexeme
+                ),
+            ));
+        } else if operator.token_type == TokenType::Bang {
+            let evaluated = self.evaluate(right)?;
+            return Ok(self.is_truthy(&evaluated));
+        }
+        Err(format!(
+            "Error occur when interpreter at line {} at {} for no matching unary operator.",
+            operator.line_number, operator.lexeme
+        )
+        .into())
+    }
+
+    // brief: Evaluate the callee and the arguments, and call it.
+    // input:
+    // output:
+    fn call_expr(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let callee = self.evaluate(callee)?;
+
+        let lazy = matches!(&callee, ExprLiteral::FunctionLiteral(f) if f.lazy_arguments());
+        let arguments: Result<Vec<ExprLiteral>, RuntimeError> = arguments
+            .iter()
+            .map(|x| {
+                if lazy {
+                    self.evaluate_lazily(x)
+                } else {
+                    self.evaluate(x)
+                }
+            })
+            .collect();
+
+        if let ExprLiteral::FunctionLiteral(mut f) = callee {
+            let args = arguments?;
+            if !f.accepts(args.len()) {
+                return Err(RuntimeError::new(
+                    ErrorCode::ArityMismatch,
+                    format!(
+                        "Error occur , function expect {} args, but got {} at line: {}.",
+                        f.arity(),
+                        args.len(),
+                        paren.line_number
+                    ),
+                ));
+            }
+            if self.stack_used() > self.max_stack {
+                return Err(format!(
+                    "Error occur when interpreter at line {} at {} for calls nested too deep, {} calls are running.",
+                    paren.line_number,
+                    f.name(),
+                    self.call_stack.len()
+                ).into());
+            }
+            self.record_event(|| TraceEvent::Call {
+                line: paren.line_number,
+                name: f.name(),
+            });
+            self.call_stack.push((f.name(), paren.line_number));
+            let result = f.call(self, args);
+            self.call_stack.pop();
+            self.clear_lookup_cache(); // the callee ran in its own environment.
+            return result;
+        }
+        let message = format!(
+            "Error occur when interpreter a function at line : {} at {}. Expected FunctionLiteral, got others.",
+            paren.line_number, paren.lexeme
+        );
+        Err(RuntimeError::new(ErrorCode::TypeMismatch, message))
+    }
+
+    // brief: Evaluate both operands, then apply the operator.
+    // input:
+    // output:
+    fn binary_expr(
+        &mut self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        if self.chained_comparisons && is_comparison(operator) {
+            let left_operand = self.evaluate(left)?; // recursively.
+
+            // a < b < c is ( a < b ) < c, so a failed ( a < b ) is passed on as false, and c is
+            // not evaluated. A false which isn't the result of a comparison is still an operand.
+            let left_is_chain =
+                matches!(left, Expr::Binary { operator, .. } if is_comparison(operator));
+            if left_is_chain && left_operand == ExprLiteral::False {
+                return Ok(ExprLiteral::False);
+            }
+            let right_operand = self.evaluate(right)?; // recursively.
+            let compared = self.binary_operation(left_operand, operator, right_operand.clone())?;
+            if compared == ExprLiteral::True {
+                return Ok(right_operand);
+            }
+            return Ok(ExprLiteral::False);
+        }
+
+        let left_operand = self.evaluate(left)?; // recursively.
+        let right_operand = self.evaluate(right)?; // recursively.
+        self.binary_operation(left_operand, operator, right_operand)
+    }
+
+    // brief: Evaluate a thunk the first time it is read, and keep the value for the later reads.
+    //        It runs in a copy of the scope where `lazy` was, so assignments to outer variables
+    //        inside it are not seen outside. Other values are given back as they are.
+    // input:
+    // output:
+    fn force(&mut self, value: ExprLiteral) -> Result<ExprLiteral, RuntimeError> {
+        let thunk = match value {
+            ExprLiteral::Thunk(t) => t,
+            other => return Ok(other),
+        };
+        let (expression, environment) = match &*thunk.borrow() {
+            ThunkState::Done(v) => return Ok(v.clone()),
+            ThunkState::Pending {
+                expression,
+                environment,
+            } => (expression.clone(), environment.clone()),
+        };
+
+        let previous = std::mem::replace(&mut self.environment, environment);
+        self.clear_lookup_cache();
+        let result = self.evaluate(&expression);
+        self.environment = previous; // also when an error occurs, then it is tried again next time.
+        self.clear_lookup_cache();
+
+        let result = self.force(result?)?; // `lazy lazy expr` is forced all the way.
+        *thunk.borrow_mut() = ThunkState::Done(result.clone());
+        Ok(result)
+    }
+
+    // brief: Whether a match pattern fits the value.
+    // input:
+    // output:
+    fn pattern_matches(pattern: &Pattern, value: &ExprLiteral) -> bool {
+        match pattern {
+            Pattern::Wildcard | Pattern::Binding(_) => true,
+            Pattern::Literal(v) => v.is_equal(value),
+            Pattern::Type { type_name, .. } => {
+                matches!(
+                    (type_name.lexeme.as_str(), value),
+                    ("number", ExprLiteral::NumberLiteral(_))
+                        | ("string", ExprLiteral::StringLiteral(_))
+                        | ("bool", ExprLiteral::True | ExprLiteral::False)
+                        | ("nil", ExprLiteral::Nil)
+                        | ("function", ExprLiteral::FunctionLiteral(_))
+                ) || Self::bigint_matches(&type_name.lexeme, value)
+            }
+        }
+    }
+
+    #[cfg(feature = "bignum")]
+    fn bigint_matches(type_name: &str, value: &ExprLiteral) -> bool {
+        type_name == "bigint" && matches!(value, ExprLiteral::BigIntLiteral(_))
+    }
+
+    #[cfg(not(feature = "bignum"))]
+    fn bigint_matches(_type_name: &str, _value: &ExprLiteral) -> bool {
+        false
+    }
+
+    // brief: Evaluate body with name bound to the initializer, in a scope of its own.
+    // input:
+    // output:
+    fn let_in_value(
+        &mut self,
+        name: &Token,
+        initializer: &Expr,
+        body: &Expr,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        let value = self.evaluate_lazily(initializer)?;
+        self.environment = Environment::new(Some(Box::new(self.environment.clone())));
+        self.environment.define(name.lexeme.clone(), value);
+        self.clear_lookup_cache();
+        let result = self.evaluate(body);
+        self.leave_scope();
+        self.clear_lookup_cache(); // name may shadow an outer variable.
+        result
+    }
+
+    // brief: The value of the first branch whose condition is true, the later conditions are not
+    //        evaluated.
+    // input:
+    // output:
+    fn when_value(
+        &mut self,
+        branches: &Vec<(Expr, Expr)>,
+        else_branch: &Option<Box<Expr>>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        for (condition, value) in branches {
+            let condition = self.evaluate(condition)?;
+            if self.is_truthy(&condition) == ExprLiteral::True {
+                return self.evaluate(value);
+            }
+        }
+        match else_branch {
+            Some(v) => self.evaluate(v),
+            None => Ok(ExprLiteral::Nil),
+        }
+    }
+
+    // brief: Run the statements of a block expression and give its value.
+    // input:
+    // output:
+    fn block_value(
+        &mut self,
+        brace: &Token,
+        statements: &Vec<Stmt>,
+        value: &Option<Box<Expr>>,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        for statement in statements {
+            if let IsReturn::Yes(_) | IsReturn::Break(_) = self.execute(statement)? {
+                return Err(format!(
+                    "Error occur when interpreter at line {} at {} for return or break inside a block expression.",
+                    brace.line_number, brace.lexeme
+                ).into());
+            }
+        }
+        match value {
+            Some(v) => self.evaluate(v),
+            None => Ok(ExprLiteral::Nil),
+        }
+    }
+
+    // brief: Apply a Binary operator on two evaluated operands, also used by operator values like (+).
+    // input:
+    // output:
+    pub fn binary_operation(
+        &self,
+        left_operand: ExprLiteral,
+        operator: &Token,
+        right_operand: ExprLiteral,
+    ) -> Result<ExprLiteral, RuntimeError> {
+        // BigInt is done exactly, or promoted to f64 when mixed with a number.
+        #[cfg(feature = "bignum")]
+        let (left_operand, right_operand) =
+            match bignum::binary(left_operand, operator, right_operand)? {
+                bignum::Binary::Done(value) => return Ok(value),
+                bignum::Binary::Operands(l, r) => (l, r),
+            };
+
+        match operator.token_type {
+            TokenType::Minus => {
+                if let (true, l_number, r_number) =
+                    self.check_number_operands(&left_operand, &right_operand)
+                {
+                    return Ok(ExprLiteral::NumberLiteral(l_number - r_number));
+                }
+                Err(RuntimeError::new(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "Error occur when interpreter at line {} at {} for some wrong operand.",
+                        operator.line_number, operator.lexeme
+                    ),
+                ))
+            }
+            TokenType::Slash => {
+                if let (true, l_number, r_number) =
+                    self.check_number_operands(&left_operand, &right_operand)
+                {
+                    return Ok(ExprLiteral::NumberLiteral(l_number / r_number));
+                }
+                Err(RuntimeError::new(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "Error occur when interpreter at line {} at {} for some wrong operand.",
+                        operator.line_number, operator.lexeme
+                    ),
+                ))
+            }
+            TokenType::Star => {
+                if let (true, l_number, r_number) =
+                    self.check_number_operands(&left_operand, &right_operand)
+                {
+                    return Ok(ExprLiteral::NumberLiteral(l_number * r_number));
+                }
+                Err(RuntimeError::new(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "Error occur when interpreter at line {} at {} for some wrong operand.",
+                        operator.line_number, operator.lexeme
+                    ),
+                ))
+            }
+            TokenType::Plus => match (left_operand, right_operand) {
+                (ExprLiteral::NumberLiteral(l_number), ExprLiteral::NumberLiteral(r_number)) => {
+                    Ok(ExprLiteral::NumberLiteral(l_number + r_number))
+                }
+
+                (ExprLiteral::StringLiteral(l_string), ExprLiteral::StringLiteral(r_string)) => Ok(
+                    ExprLiteral::StringLiteral(format!("{}{}", l_string, r_string)),
+                ),
+
+                _ => Err(RuntimeError::new(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "Error occur when interpreter at line {} at {} for some wrong operand.",
+                        operator.line_number, operator.lexeme
+                    ),
+                )),
+            },
+            TokenType::Greater => {
+                if let (true, l_number, r_number) =
+                    self.check_number_operands(&left_operand, &right_operand)
+                {
+                    if l_number > r_number {
+                        return Ok(ExprLiteral::True);
+                    } else {
+                        return Ok(ExprLiteral::False);
+                    }
+                }
+                Err(RuntimeError::new(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "Error occur when interpreter at line {} at {} for some wrong operand.",
+                        operator.line_number, operator.lexeme
+                    ),
+                ))
+            }
+            TokenType::GreaterEqual => {
+                if let (true, l_number, r_number) =
+                    self.check_number_operands(&left_operand, &right_operand)
+                {
+                    if l_number >= r_number {
+                        return Ok(ExprLiteral::True);
+                    } else {
+                        return Ok(ExprLiteral::False);
                     }
                 }
+                Err(RuntimeError::new(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "Error occur when interpreter at line {} at {} for some wrong operand.",
+                        operator.line_number, operator.lexeme
+                    ),
+                ))
             }
+            TokenType::Less => {
+                if let (true, l_number, r_number) =
+                    self.check_number_operands(&left_operand, &right_operand)
+                {
+                    if l_number < r_number {
+                        return Ok(ExprLiteral::True);
+                    } else {
+                        return Ok(ExprLiteral::False);
+                    }
+                }
+                Err(RuntimeError::new(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "Error occur when interpreter at line {} at {} for some wrong operand.",
+                        operator.line_number, operator.lexeme
+                    ),
+                ))
+            }
+            TokenType::LessEqual => {
+                if let (true, l_number, r_number) =
+                    self.check_number_operands(&left_operand, &right_operand)
+                {
+                    if l_number <= r_number {
+                        return Ok(ExprLiteral::True);
+                    } else {
+                        return Ok(ExprLiteral::False);
+                    }
+                }
+                Err(RuntimeError::new(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "Error occur when interpreter at line {} at {} for some wrong operand.",
+                        operator.line_number, operator.lexeme
+                    ),
+                ))
+            }
+            TokenType::EqualEqual => {
+                if left_operand == right_operand {
+                    Ok(ExprLiteral::True)
+                } else {
+                    Ok(ExprLiteral::False)
+                }
+            }
+            TokenType::BangEqual => {
+                if left_operand != right_operand {
+                    Ok(ExprLiteral::True)
+                } else {
+                    Ok(ExprLiteral::False)
+                }
+            }
+            _ => Err(format!(
+                "Error occur when interpreter at line {} at {} for no matchine Binary operator.",
+                operator.line_number, operator.lexeme
+            )
+            .into()),
         }
     }
 
@@ -446,12 +1479,54 @@ impl Interpreter {
     }
 }
 
+// brief: "<" ">" "<=" ">=", the operators which chain in Interpreter::with_chained_comparisons().
+// input:
+// output:
+fn is_comparison(operator: &Token) -> bool {
+    matches!(
+        operator.token_type,
+        TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual
+    )
+}
+
 #[cfg(test)]
 mod tests {
 
-    use super::Interpreter;
-    use super::Parser;
-    use crate::Scanner;
+    use super::{AssertSummary, Interpreter};
+    use crate::lexer::clock::{format_timestamp, Clock, FakeClock};
+    use crate::lexer::error::ErrorCode;
+    use crate::lexer::trace::TraceEvent;
+    use crate::lexer::{expr::ExprLiteral, filesystem::MemoryFileSystem, output::CaptureOutput};
+    use crate::{Parser, Scanner};
+    use std::{cell::RefCell, rc::Rc};
+
+    // brief: Scan, parse and interpreter the sources, return what the top level returns.
+    // input:
+    // output:
+    fn run(sources: &str) -> Result<ExprLiteral, String> {
+        run_on(&mut Interpreter::new(), sources)
+    }
+
+    // brief: Like run(), on an Interpreter set up by the test, which can be looked at afterwards.
+    // input:
+    // output:
+    fn run_on(interpreter: &mut Interpreter, sources: &str) -> Result<ExprLiteral, String> {
+        let tok = Scanner::new(sources.to_string()).scan_tokens()?;
+        let pas = Parser::new(tok).parse()?;
+        interpreter.interpreter(&pas)
+    }
+
+    // brief: Like run(), and also give what the sources printed.
+    // input:
+    // output:
+    fn run_printed(sources: &str) -> (String, Result<ExprLiteral, String>) {
+        let output = CaptureOutput::new();
+        let value = run_on(
+            &mut Interpreter::new().with_output(Box::new(output.clone())),
+            sources,
+        );
+        (output.out(), value)
+    }
 
     #[test]
     fn test_inter_one() {
@@ -460,7 +1535,8 @@ mod tests {
 
         let tok = scan.scan_tokens().unwrap();
 
-        let pas = Parser::new(tok).parse().unwrap();
+        // "=" needs a variable on its left side, so this is rejected by the parser now.
+        assert!(Parser::new(tok).parse().is_err());
 
         // match Interpreter::new().evaluate(&pas) {
         //     Ok(v) => {
@@ -485,7 +1561,7 @@ mod tests {
         let pas = Parser::new(tok).parse().unwrap();
 
         match Interpreter::new().interpreter(&pas) {
-            Ok(()) => {
+            Ok(_) => {
                 println!("[    PASS!     ] ---> Compile Successfully.");
             }
             Err(v) => {
@@ -505,7 +1581,7 @@ mod tests {
         let pas = Parser::new(tok).parse().unwrap();
 
         match Interpreter::new().interpreter(&pas) {
-            Ok(()) => {
+            Ok(_) => {
                 println!("[    PASS!     ] ---> Compile Successfully.");
             }
             Err(v) => {
@@ -525,7 +1601,7 @@ mod tests {
         let pas = Parser::new(tok).parse().unwrap();
 
         match Interpreter::new().interpreter(&pas) {
-            Ok(()) => {
+            Ok(_) => {
                 println!("[    PASS!     ] ---> Compile Successfully.");
             }
             Err(v) => {
@@ -546,7 +1622,7 @@ mod tests {
         let pas = Parser::new(tok).parse().unwrap();
 
         match Interpreter::new().interpreter(&pas) {
-            Ok(()) => {
+            Ok(_) => {
                 println!("[    PASS!     ] ---> Compile Successfully.");
             }
             Err(v) => {
@@ -555,6 +1631,1149 @@ mod tests {
         }
         //        dbg!(pas);
     }
+
+    #[cfg(not(feature = "bignum"))]
+    #[test]
+    fn test_inter_bignum_disabled() {
+        // Without the feature, 123n is not a number.
+        assert!(run("return 123n;").is_err());
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_inter_bignum_fact() {
+        let sources =
+            "fn fact(n) { if (n <= 1n) return 1n; return n * fact(n - 1n); } return fact(50n);";
+
+        let expected = "30414093201713378043612608166064768844377641568960512000000000000";
+        assert_eq!(run(sources).unwrap().two_string(), expected);
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_inter_bignum_compare() {
+        let sources = "let a = 100000000000000000000000000001n; let b = 100000000000000000000000000000n; return a > b and b < a and a != b and a - 1n == b;";
+        assert_eq!(run(sources).unwrap(), ExprLiteral::True);
+
+        // Mixed with f64, the BigInt is promoted to a number.
+        assert_eq!(
+            run("return 3n * 1.5;").unwrap(),
+            ExprLiteral::NumberLiteral(4.5)
+        );
+        assert_eq!(run("return 2n < 2.5;").unwrap(), ExprLiteral::True);
+        assert!(run("return 1n / 0n;").is_err());
+    }
+
+    #[test]
+    fn test_inter_profile() {
+        let sources = "let i = 0.0; while (i < 200000.0) { i = i + 1.0; } i = 0.0;";
+        let tok = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+
+        let mut interpreter = Interpreter::new().with_profile(true);
+        interpreter.interpreter(&pas).unwrap();
+
+        let report = interpreter.profile_report().unwrap();
+        assert_eq!(report.len(), 3);
+        for (i, entry) in report.iter().enumerate() {
+            assert_eq!(entry.index, i); // One entry per statement, in order.
+        }
+        assert!(report[1].elapsed > report[2].elapsed); // The loop is measurably slower than a single assignment.
+
+        assert!(Interpreter::new().profile_report().is_none());
+    }
+
+    #[test]
+    fn test_inter_assert() {
+        assert!(run("let a = 3.0; let b = 7.0; assert a + b == 10.0;").is_ok());
+
+        let err = run("let a = 3.0; let b = 4.0;\n assert a + b == 10.0;").unwrap_err();
+        assert_eq!(
+            err,
+            "Assertion failed at line 2: ( ( a + b ) == 10 ), where a = 3, b = 4, ( a + b ) = 7."
+        );
+
+        // Right side of "and" is short-circuited, so it's neither evaluated nor recorded.
+        let err = run("let a = 1.0; assert a > 2.0 and undefined_variable;").unwrap_err();
+        assert!(err.contains("a = 1, ( a > 2 ) = false"));
+
+        // The callee is evaluated before the arguments, the same as outside an assert.
+        let sources =
+            "fn id(x) = x; fn pick() { print \"callee\"; return id; } fn one() { print \"argument\"; return 1.0; } assert pick()(one()) == 1.0;";
+        assert_eq!(
+            run_printed(sources),
+            ("callee\nargument\n".to_string(), Ok(ExprLiteral::Nil))
+        );
+    }
+
+    #[test]
+    fn test_inter_chained_comparisons() {
+        let chained = |sources: &str| {
+            run_on(
+                &mut Interpreter::new().with_chained_comparisons(true),
+                sources,
+            )
+        };
+
+        let number = ExprLiteral::NumberLiteral;
+        assert_eq!(
+            chained("let x = 5.0; return 0.0 < x < 10.0;"),
+            Ok(number(10.0))
+        );
+        assert_eq!(chained("let x = 5.0; return 0.0 < x;"), Ok(number(5.0)));
+        // Fails at the first comparison, the false is carried through the second one.
+        assert_eq!(
+            chained("let x = -1.0; return 0.0 < x < 10.0;"),
+            Ok(ExprLiteral::False)
+        );
+        // Fails at the second comparison.
+        assert_eq!(
+            chained("let x = 12.0; return 0.0 < x < 10.0;"),
+            Ok(ExprLiteral::False)
+        );
+        assert_eq!(chained("return 1.0 <= 1.0 <= 2.0;"), Ok(number(2.0)));
+        assert_eq!(chained("return 3.0 > 2.0 >= 2.0;"), Ok(number(2.0)));
+
+        // A failed comparison stops the chain, so f() is not called.
+        let sources =
+            "let x = -1.0; fn f() { print \"called\"; return 10.0; } return 0.0 < x < f();";
+        let output = CaptureOutput::new();
+        let mut interpreter = Interpreter::new()
+            .with_chained_comparisons(true)
+            .with_output(Box::new(output.clone()));
+        assert_eq!(run_on(&mut interpreter, sources), Ok(ExprLiteral::False));
+        assert_eq!(output.out(), "");
+
+        // Only a comparison is a chain, a false written by hand is a wrong operand.
+        assert!(chained("return false < 1.0;").is_err());
+        assert!(chained("let b = false; return b < 1.0;").is_err());
+
+        // Without the mode, the boolean result can't be compared with a number.
+        assert!(run("let x = 5.0; return 0.0 < x < 10.0;").is_err());
+        assert_eq!(run("return 0.0 < 5.0;"), Ok(ExprLiteral::True));
+    }
+
+    #[test]
+    fn test_inter_debug() {
+        let sources = "let a = 1.0; let b = \"hi\";\nfn f(x) {\n debug;\n}\nf(2.0);";
+        let tok = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+
+        let output = CaptureOutput::new();
+        Interpreter::new()
+            .with_output(Box::new(output.clone()))
+            .interpreter(&pas)
+            .unwrap();
+
+        let err = output.err();
+        assert!(err.contains("[debug] line 3\n"));
+        assert!(err.contains("[debug]   a = 1\n"));
+        assert!(err.contains("[debug]   b = hi\n"));
+        assert!(err.contains("[debug]   x = 2\n"));
+        assert!(err.contains("[debug]   clock = <fn clock>\n"));
+        assert!(err.contains("[debug] call stack: <script> -> f (line 5)\n"));
+        assert_eq!(output.out(), "");
+    }
+
+    #[test]
+    fn test_inter_concise_function() {
+        assert_eq!(
+            run("fn square(x) = x * x; return square(3.0) == 9.0;"),
+            Ok(ExprLiteral::True)
+        );
+        assert_eq!(
+            run("fn fact(n) = n <= 1.0 and 1.0 or n * fact(n - 1.0); return fact(5.0);"),
+            Ok(ExprLiteral::NumberLiteral(120.0))
+        );
+        assert_eq!(
+            run("fn add(a, b) = a + b; fn twice(x) = add(x, x); return twice(4.0);"),
+            Ok(ExprLiteral::NumberLiteral(8.0))
+        );
+        assert!(run("fn square(x) = x * x").is_err());
+    }
+
+    #[test]
+    fn test_inter_pipe() {
+        assert_eq!(
+            run("return 5.0 |> abs |> to_string;"),
+            Ok(ExprLiteral::StringLiteral("5".to_string()))
+        );
+        assert_eq!(
+            run("fn inc(x) = x + 1.0; fn double(x) = x * 2.0; let y = -3.0 |> abs |> inc |> double; return y;"),
+            Ok(ExprLiteral::NumberLiteral(8.0))
+        );
+        // Lower than "or", so the whole left side is piped.
+        assert_eq!(
+            run("return nil or -2.0 |> abs;"),
+            Ok(ExprLiteral::NumberLiteral(2.0))
+        );
+
+        let err = run("let x = 1.0; return 5.0 |> x;").unwrap_err();
+        assert!(err.contains("Expected FunctionLiteral"));
+        assert!(run("return 5.0 |> 3.0;").is_err());
+        assert!(run("return 5.0 | abs;").is_err());
+    }
+
+    #[test]
+    fn test_inter_curry() {
+        let add = "fn add(a, b) = a + b; fn add3(a, b, c) = a + b + c;";
+        let number = |v| Ok(ExprLiteral::NumberLiteral(v));
+
+        assert_eq!(
+            run(&format!(
+                "{} let add5 = curry(add)(5.0); return add5(3.0);",
+                add
+            )),
+            number(8.0)
+        );
+        assert_eq!(
+            run(&format!("{} return curry(add3)(1.0)(2.0)(3.0);", add)),
+            number(6.0)
+        );
+        assert_eq!(
+            run(&format!("{} return curry(add3)(1.0, 2.0)(3.0);", add)),
+            number(6.0)
+        );
+        assert_eq!(
+            run(&format!(
+                "{} let f = curry(add3); let g = f(1.0); f(10.0); return g(2.0, 3.0);",
+                add
+            )),
+            number(6.0)
+        );
+
+        // Over-applying is an arity error.
+        let err = run(&format!(
+            "{} let add5 = curry(add)(5.0); return add5(3.0, 4.0);",
+            add
+        ))
+        .unwrap_err();
+        assert!(err.contains("function expect 1 args, but got 2"));
+        assert!(run(&format!("{} return curry(add)();", add)).is_err());
+        assert!(run("return curry(1.0);").is_err());
+
+        // A function without parameters is called by the first call.
+        assert_eq!(
+            run("fn f() { return 1.0; } return curry(f)();"),
+            Ok(ExprLiteral::NumberLiteral(1.0))
+        );
+        assert!(run("fn f() { return 1.0; } return curry(f)(2.0);").is_err());
+    }
+
+    #[test]
+    fn test_inter_print_sep_end() {
+        let printed = |sources: &str| {
+            let (out, value) = run_printed(sources);
+            value.unwrap();
+            out
+        };
+
+        assert_eq!(printed("print 1.0, 2.0 sep \", \" end \"\";"), "1, 2");
+        assert_eq!(printed("print 1.0, \"a\", nil;"), "1 a nil\n");
+        assert_eq!(printed("print 1.0, 2.0 sep \"-\"; print 3.0;"), "1-2\n3\n");
+        assert_eq!(printed("print 1.0 end \"!\"; print 2.0;"), "1!2\n");
+        // Just a variable called sep.
+        assert_eq!(printed("let sep = 1.0; print sep;"), "1\n");
+    }
+
+    #[test]
+    fn test_inter_memo() {
+        let fib = "@memo fn fib(n) { if (n < 2.0) return n; return fib(n - 1.0) + fib(n - 2.0); }";
+        assert_eq!(
+            run(&format!("{} return fib(35.0);", fib)),
+            Ok(ExprLiteral::NumberLiteral(9227465.0))
+        );
+        assert_eq!(
+            run(&format!("{} return fib(10.0) + fib(10.0);", fib)),
+            Ok(ExprLiteral::NumberLiteral(110.0))
+        );
+
+        // The body only runs once for the same arguments.
+        let sources = "@memo fn sq(x) { print x; return x * x; } sq(2.0); sq(2.0); sq(\"a\" + \"\"); sq(3.0); sq(2.0);";
+        assert_eq!(run_printed(sources).0, "2\na\n");
+
+        assert!(run("@cache fn f() {}").is_err());
+        assert!(run("@memo let a = 1.0;").is_err());
+    }
+
+    #[test]
+    fn test_inter_lookup_cache() {
+        // Assignment in the middle of an expression is seen by the reads after it.
+        assert_eq!(
+            run("let a = 1.0; return a + (a = 10.0) + a;"),
+            Ok(ExprLiteral::NumberLiteral(21.0))
+        );
+        assert_eq!(
+            run("let a = 1.0; let b = a + (a = a + 1.0) + (a = a + 1.0) + a; return b * 10.0 + a;"),
+            Ok(ExprLiteral::NumberLiteral(93.0))
+        );
+        // The callee reads its own a, the caller still reads the outer one.
+        assert_eq!(
+            run("let a = 1.0; fn f(a) { return a * 2.0; } return a + f(5.0) + a;"),
+            Ok(ExprLiteral::NumberLiteral(12.0))
+        );
+        // Shadowing in a block doesn't leak into the enclosing condition.
+        assert_eq!(
+            run("let x = 1.0; let n = 0.0; while (n < 3.0 and x == 1.0) { let x = 100.0; n = n + x / x; } return x + n;"),
+            Ok(ExprLiteral::NumberLiteral(4.0))
+        );
+    }
+
+    #[test]
+    #[ignore] // cargo test lookup_cache_bench --release -- --ignored --nocapture
+    fn test_inter_lookup_cache_bench() {
+        let mut sources = "let a = 1.0; let i = 0.0;".to_string();
+        for _ in 0..20 {
+            sources.push_str(" {");
+        }
+        sources.push_str(" while (i < 20000.0) i = i + a - a + a - a + a - a + a - a + a - a + a - a + a - a + a;");
+        for _ in 0..20 {
+            sources.push_str(" }");
+        }
+        sources.push_str(" return i;");
+        let tok = Scanner::new(sources).scan_tokens().unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+
+        let mut elapsed = vec![];
+        for cache in [false, true] {
+            let start = std::time::Instant::now();
+            let result = Interpreter::new()
+                .with_lookup_cache(cache)
+                .interpreter(&pas);
+            elapsed.push(start.elapsed());
+            assert_eq!(result, Ok(ExprLiteral::NumberLiteral(20000.0)));
+        }
+        println!(
+            "without cache: {:?}, with cache: {:?}",
+            elapsed[0], elapsed[1]
+        );
+        assert!(elapsed[1] < elapsed[0]);
+    }
+
+    #[test]
+    fn test_inter_files() {
+        let run_with = |sources: &str, filesystem: &MemoryFileSystem| {
+            run_on(
+                &mut Interpreter::new().with_filesystem(Box::new(filesystem.clone())),
+                sources,
+            )
+        };
+        let filesystem = MemoryFileSystem::new();
+
+        let sources = "write_file(\"a.txt\", \"old\"); write_file(\"a.txt\", \"hello \" + \"world\"); return read_file(\"a.txt\");";
+        assert_eq!(
+            run_with(sources, &filesystem),
+            Ok(ExprLiteral::StringLiteral("hello world".to_string()))
+        );
+        assert_eq!(filesystem.file("a.txt"), Some("hello world".to_string()));
+        assert_eq!(
+            run_with("return read_file(\"a.txt\");", &filesystem),
+            Ok(ExprLiteral::StringLiteral("hello world".to_string()))
+        );
+
+        let err = run_with("let a = 1.0;\nprint read_file(\"b.txt\");", &filesystem).unwrap_err();
+        assert_eq!(
+            err,
+            "Error occur when interpreter at line 2 at read_file for No such file b.txt."
+        );
+        let err = run_with("write_file(\"dir/\", \"x\");", &filesystem).unwrap_err();
+        assert!(err.starts_with("Error occur when interpreter at line 1 at write_file for"));
+        let err = run_with("write_file(\"c.txt\", 1.0);", &filesystem).unwrap_err();
+        assert!(err.contains("write_file expect a string, but got 1 at line: 1."));
+    }
+
+    #[test]
+    fn test_inter_random() {
+        let draw = "fn draw() { let s = \"\"; let i = 0.0; while (i < 5.0) { s = s + to_string(random()) + \",\"; i = i + 1.0; } return s; }";
+        let first = run(&format!("{} seed(42.0); return draw();", draw));
+        assert_eq!(first, run(&format!("{} seed(42.0); return draw();", draw)));
+        assert_ne!(first, run(&format!("{} seed(43.0); return draw();", draw)));
+        assert_eq!(
+            run(&format!(
+                "{} seed(42.0); let a = draw(); seed(42.0); return a == draw();",
+                draw
+            )),
+            Ok(ExprLiteral::True)
+        );
+
+        // Without seed() in the script, with_seed() makes the run reproducible.
+        let tok = Scanner::new(format!("{} return draw();", draw))
+            .scan_tokens()
+            .unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+        assert_eq!(
+            Interpreter::new().with_seed(7).interpreter(&pas),
+            Interpreter::new().with_seed(7).interpreter(&pas)
+        );
+
+        assert_eq!(
+            run("seed(1.0); let i = 0.0; let ok = true; while (i < 200.0) { let r = random(); if (r < 0.0 or r >= 1.0) ok = false; let n = randint(-2.0, 2.0); if (n < -2.0 or n > 2.0 or n != randint(n, n)) ok = false; i = i + 1.0; } return ok;"),
+            Ok(ExprLiteral::True)
+        );
+        assert!(run("randint(1.5, 3.0);").is_err());
+        assert!(run("randint(3.0, 1.0);").is_err());
+        assert!(run("seed(\"a\");").is_err());
+    }
+
+    #[test]
+    fn test_inter_if_expression() {
+        assert_eq!(
+            run("let x = if (true) { 1.0 } else { 2.0 }; return x;"),
+            Ok(ExprLiteral::NumberLiteral(1.0))
+        );
+        assert_eq!(
+            run("let y = if (false) { 1.0 }; return y;"),
+            Ok(ExprLiteral::Nil)
+        );
+        assert_eq!(
+            run("let n = 5.0; return if (n < 0.0) { \"neg\" } else if (n == 0.0) { \"zero\" } else { \"pos\" };"),
+            Ok(ExprLiteral::StringLiteral("pos".to_string()))
+        );
+        // Statements run before the value, in their own scope.
+        assert_eq!(
+            run("let a = 1.0; let b = if (a > 0.0) { let a = 10.0; a = a + 1.0; a * 2.0 }; return b + a;"),
+            Ok(ExprLiteral::NumberLiteral(23.0))
+        );
+        assert_eq!(
+            run("let a = 1.0; let b = a + { let a = 5.0; a } + a; return b;"),
+            Ok(ExprLiteral::NumberLiteral(7.0))
+        );
+        // The last expression needs to be without ";" to be the value.
+        assert_eq!(run("return if (true) { 1.0; };"), Ok(ExprLiteral::Nil));
+        assert_eq!(
+            run("fn sign(x) = if (x < 0.0) { -1.0 } else { 1.0 }; return sign(-3.0) + sign(2.0) * 10.0;"),
+            Ok(ExprLiteral::NumberLiteral(9.0))
+        );
+
+        assert!(run("let x = if (true) 1.0;").is_err());
+        assert!(run("fn f() { let x = if (true) { return 1.0; }; } f();").is_err());
+    }
+
+    #[derive(Debug, Clone)]
+    struct MyPanic;
+
+    impl crate::lexer::callable::Callable for MyPanic {
+        fn call(
+            &mut self,
+            _interpreter: &mut Interpreter,
+            _arguments: Vec<ExprLiteral>,
+        ) -> Result<ExprLiteral, crate::lexer::error::RuntimeError> {
+            panic!("boom")
+        }
+
+        fn arity(&self) -> usize {
+            0
+        }
+
+        fn name(&self) -> String {
+            "explode".to_string()
+        }
+        fn clone_box(&self) -> Box<dyn crate::lexer::callable::Callable> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_inter_no_panic() {
+        let parse = |sources: &str| {
+            let tok = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+            Parser::new(tok).parse().unwrap()
+        };
+
+        // Runaway recursion is an error instead of a stack overflow.
+        let err = run("fn f(n) { return f(n + 1.0); } f(0.0);").unwrap_err();
+        assert!(err.contains("at line 1 at f for calls nested too deep"));
+        let deep = "fn f(n) = if (n < 1.0) { 0.0 } else { ((((1.0 + f(n - 1.0))))) };";
+        assert!(run(&format!("{} return f(10000.0);", deep))
+            .unwrap_err()
+            .contains("for calls nested too deep"));
+        assert_eq!(
+            run(&format!("{} return f(30.0);", deep)),
+            Ok(ExprLiteral::NumberLiteral(30.0))
+        );
+
+        // After an error in a block or a function the scopes are left, so the Interpreter can go on.
+        let mut interpreter = Interpreter::new();
+        assert!(interpreter
+            .interpreter(&parse("let a = 1.0; { let a = 2.0; missing; }"))
+            .is_err());
+        assert!(interpreter
+            .interpreter(&parse("fn g() { let z = 1.0; missing(); } g();"))
+            .is_err());
+        assert_eq!(
+            interpreter.interpreter(&parse("return a;")),
+            Ok(ExprLiteral::NumberLiteral(1.0))
+        );
+        assert!(interpreter.interpreter(&parse("return z;")).is_err());
+
+        // A panicking native of the host comes back as an error.
+        let mut interpreter = Interpreter::new().with_native(Box::new(MyPanic));
+        assert_eq!(
+            interpreter.interpreter(&parse("explode();")),
+            Err("Internal error occur when interpreter: boom.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_inter_match() {
+        let describe = "fn describe(v) { match (v) { number n => return n * 2.0; string s => { let t = s + s; return t; } nil => return \"nothing\"; _ => return \"other\"; } }";
+        assert_eq!(
+            run(&format!("{} return describe(4.0);", describe)),
+            Ok(ExprLiteral::NumberLiteral(8.0))
+        );
+        assert_eq!(
+            run(&format!("{} return describe(\"ab\");", describe)),
+            Ok(ExprLiteral::StringLiteral("abab".to_string()))
+        );
+        assert_eq!(
+            run(&format!("{} return describe(nil);", describe)),
+            Ok(ExprLiteral::StringLiteral("nothing".to_string()))
+        );
+        assert_eq!(
+            run(&format!("{} return describe(true);", describe)),
+            Ok(ExprLiteral::StringLiteral("other".to_string()))
+        );
+        assert_eq!(
+            run(&format!("{} return describe(clock);", describe)),
+            Ok(ExprLiteral::StringLiteral("other".to_string()))
+        );
+
+        // Literal patterns, only the first matched arm runs, the binding ends with the arm.
+        let sources = "let n = 0.0; let x = -1.0; match (x) { 1.0 => n = 1.0; -1.0 => n = 2.0; number x => n = 3.0; } match (true) { bool b => x = b; } return n;";
+        assert_eq!(run(sources), Ok(ExprLiteral::NumberLiteral(2.0)));
+        assert_eq!(
+            run("let x = 5.0; match (\"s\") { number x => x = 1.0; function f => x = 2.0; } return x;"),
+            Ok(ExprLiteral::NumberLiteral(5.0))
+        );
+        assert_eq!(
+            run("let x = 5.0; match (1.0) { number y => x = y + 1.0; } return x;"),
+            Ok(ExprLiteral::NumberLiteral(2.0))
+        );
+
+        assert!(run("match (1.0) { integer i => print i; }").is_err());
+        assert!(run("match (1.0) { number n print n; }").is_err());
+    }
+
+    #[test]
+    fn test_inter_labeled_block() {
+        let sources = "let steps = \"\"; 'outer: { steps = steps + \"a\"; if (true) { steps = steps + \"b\"; break 'outer; } steps = steps + \"c\"; } steps = steps + \"d\"; return steps;";
+        assert_eq!(
+            run(sources),
+            Ok(ExprLiteral::StringLiteral("abd".to_string()))
+        );
+
+        // The inner break only leaves the inner block, a labeled loop is left from its body.
+        let sources = "let s = \"\"; 'a: { 'b: { s = s + \"1\"; break 'b; s = s + \"x\"; } s = s + \"2\"; break 'a; s = s + \"y\"; } return s;";
+        assert_eq!(
+            run(sources),
+            Ok(ExprLiteral::StringLiteral("12".to_string()))
+        );
+        let sources = "let i = 0.0; 'loop: while (true) { i = i + 1.0; if (i == 5.0) break 'loop; } return i;";
+        assert_eq!(run(sources), Ok(ExprLiteral::NumberLiteral(5.0)));
+        let sources = "fn f() { 'x: { return 1.0; } return 2.0; } return f();";
+        assert_eq!(run(sources), Ok(ExprLiteral::NumberLiteral(1.0)));
+        let sources = "fn f() { if (true) { return nil; } return 2.0; } return f();";
+        assert_eq!(run(sources), Ok(ExprLiteral::Nil));
+
+        assert!(run("'a: { break 'b; }").is_err());
+        assert!(run("break 'a;").is_err());
+        assert!(run("'a: { fn f() { break 'a; } }").is_err());
+        assert!(run("'a: { 'a: { } }").is_err());
+        assert!(run("'a: print 1.0;").is_err());
+        assert!(run("'a: { let x = { break 'a; }; }").is_err());
+    }
+
+    #[test]
+    fn test_inter_assert_approx() {
+        assert_eq!(run("return 0.1 + 0.2 == 0.3;"), Ok(ExprLiteral::False));
+        assert_eq!(
+            run("assert_approx(0.1 + 0.2, 0.3, 0.000000001); return 1.0;"),
+            Ok(ExprLiteral::NumberLiteral(1.0))
+        );
+        assert_eq!(
+            run("let a = 1.0;\nassert_approx(a, 1.5, 0.25);"),
+            Err(
+                "Assertion failed at line 2: assert_approx(1, 1.5, 0.25), the difference is 0.5."
+                    .to_string()
+            )
+        );
+        assert!(run("assert_approx(1.0, \"1\", 0.1);").is_err());
+        assert!(run("assert_approx(1.0, 1.0, -0.1);").is_err());
+    }
+
+    #[test]
+    fn test_inter_operator_value() {
+        // There is no array yet, so reduce is written for three values.
+        let reduce = "fn reduce3(a, b, c, f, init) { return f(f(f(init, a), b), c); }";
+        assert_eq!(
+            run(&format!(
+                "{} return reduce3(1.0, 2.0, 3.0, (+), 0.0) == 6.0;",
+                reduce
+            )),
+            Ok(ExprLiteral::True)
+        );
+        assert_eq!(
+            run(&format!(
+                "{} return reduce3(2.0, 3.0, 4.0, (*), 1.0);",
+                reduce
+            )),
+            Ok(ExprLiteral::NumberLiteral(24.0))
+        );
+        assert_eq!(
+            run("return to_string((+));"),
+            Ok(ExprLiteral::StringLiteral("<fn +>".to_string()))
+        );
+        assert_eq!(
+            run("let minus = (-); return minus(5.0, 2.0);"),
+            Ok(ExprLiteral::NumberLiteral(3.0))
+        );
+        assert_eq!(run("return (<=)(1.0, 1.0);"), Ok(ExprLiteral::True));
+        assert_eq!(
+            run("return (+)(\"a\", \"b\");"),
+            Ok(ExprLiteral::StringLiteral("ab".to_string()))
+        );
+        // A parenthesized expression is still a grouping.
+        assert_eq!(run("return (-1.0);"), Ok(ExprLiteral::NumberLiteral(-1.0)));
+        assert!(run("(+)(1.0);").is_err());
+        assert!(run("(-)(1.0, \"a\");").is_err());
+    }
+
+    #[test]
+    fn test_inter_lazy() {
+        let slow = "fn slow(x) { print \"slow\"; return x * 2.0; }";
+
+        // Only runs when it is read, and only once.
+        assert_eq!(
+            run_printed(&format!(
+                "{} let a = lazy slow(2.0) + 1.0; print \"before\"; print a; print a; return a;",
+                slow
+            )),
+            (
+                "before\nslow\n5\n5\n".to_string(),
+                Ok(ExprLiteral::NumberLiteral(5.0))
+            )
+        );
+        // Never read, never run.
+        assert_eq!(
+            run_printed(&format!(
+                "{} fn pick(c, a, b) {{ if (c) return a; return b; }} return pick(true, 1.0, lazy slow(3.0));",
+                slow
+            )),
+            (String::new(), Ok(ExprLiteral::NumberLiteral(1.0)))
+        );
+        // Copies of a thunk share the cached value.
+        assert_eq!(
+            run_printed(&format!(
+                "{} let a = lazy slow(1.0); let b = a; print a; print b;",
+                slow
+            ))
+            .0,
+            "slow\n2\n2\n"
+        );
+        assert_eq!(
+            run("let x = 1.0; let a = lazy x + 1.0; x = 10.0; return a;"),
+            Ok(ExprLiteral::NumberLiteral(2.0))
+        );
+        assert!(run("let a = lazy nothing; return a;").is_err());
+
+        // Used right away, a lazy value is forced like a variable holding it.
+        assert_eq!(
+            run_printed("if (lazy false) print \"yes\"; else print \"no\";").0,
+            "no\n"
+        );
+        assert_eq!(
+            run("fn f() { return lazy 2.0; } return f() + 1.0;"),
+            Ok(ExprLiteral::NumberLiteral(3.0))
+        );
+        assert_eq!(
+            run_printed("match (lazy 3.0) { number n => print n; _ => print \"other\"; }").0,
+            "3\n"
+        );
+        assert_eq!(
+            run("return abs(lazy -3.0);"),
+            Ok(ExprLiteral::NumberLiteral(3.0))
+        );
+        assert_eq!(run_printed("print lazy 1.0;").0, "1\n");
+        assert_eq!(
+            run("return -(lazy 1.0);"),
+            Ok(ExprLiteral::NumberLiteral(-1.0))
+        );
+    }
+
+    #[test]
+    fn test_inter_discard() {
+        assert_eq!(
+            run_printed("fn side(x) { print x; return x; } let _ = side(1.0); let _ = side(2.0);"),
+            ("1\n2\n".to_string(), Ok(ExprLiteral::Nil))
+        );
+        assert_eq!(
+            run("let _ = 1.0; { let _ = \"a\"; _ = 2.0; } _ = 3.0; return 4.0;"),
+            Ok(ExprLiteral::NumberLiteral(4.0))
+        );
+        assert_eq!(
+            run("fn second(_, _, c) = c; return second(1.0, 2.0, 3.0);"),
+            Ok(ExprLiteral::NumberLiteral(3.0))
+        );
+        assert_eq!(
+            run("let _ = 1.0; return _;"),
+            Err("Parsering error occurs for cannot read discard '_' at line 1.".to_string())
+        );
+        assert!(run("let _ = 1.0; print _ + 1.0;").is_err());
+        // Still the wildcard of match.
+        assert_eq!(
+            run("let r = 0.0; match (5.0) { 1.0 => r = 1.0; _ => r = 2.0; } return r;"),
+            Ok(ExprLiteral::NumberLiteral(2.0))
+        );
+    }
+
+    #[test]
+    fn test_inter_assert_summary() {
+        let sources = "let a = 1.0;\nassert a == 1.0;\nassert a == 2.0;\nassert_approx(a, 1.1, 0.5);\nassert_approx(a, 3.0, 0.5);\nassert a < 2.0;\nprint \"done\";";
+        let tok = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+
+        let output = CaptureOutput::new();
+        let mut interpreter = Interpreter::new()
+            .with_output(Box::new(output.clone()))
+            .with_assert_summary(true);
+        assert_eq!(
+            interpreter.interpreter(&pas),
+            Err("2 of 5 assertions failed.".to_string())
+        );
+        let summary = interpreter.assert_summary().unwrap();
+        assert_eq!(summary.passed, 3);
+        assert_eq!(summary.failures.len(), 2);
+        assert_eq!(
+            output.out(),
+            "done\n3 passed, 2 failed\n  Assertion failed at line 3: ( a == 2 ), where a = 1.\n  Assertion failed at line 5: assert_approx(1, 3, 0.5), the difference is 2.\n"
+        );
+
+        // All passed, and the counts start again on the next run.
+        let tok = Scanner::new("assert true; assert 1.0 < 2.0;".to_string())
+            .scan_tokens()
+            .unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+        assert_eq!(interpreter.interpreter(&pas), Ok(ExprLiteral::Nil));
+        assert_eq!(
+            interpreter.assert_summary(),
+            Some(&AssertSummary {
+                passed: 2,
+                failures: vec![]
+            })
+        );
+
+        // Without the mode the first failure stops the script.
+        assert!(run(sources).is_err());
+    }
+
+    #[test]
+    fn test_inter_let_in() {
+        assert_eq!(
+            run("return let a = 2.0 in a * a;"),
+            Ok(ExprLiteral::NumberLiteral(4.0))
+        );
+        assert_eq!(
+            run("let a = 2.0 in a * a; return a;"),
+            Err("Undefined variable a.".to_string())
+        );
+        assert_eq!(
+            run("let a = 1.0; let b = (let a = 10.0 in a + 1.0) + a; return b * a;"),
+            Ok(ExprLiteral::NumberLiteral(12.0))
+        );
+        assert_eq!(
+            run("return let a = 2.0 in let b = a + 1.0 in a * b;"),
+            Ok(ExprLiteral::NumberLiteral(6.0))
+        );
+        assert_eq!(
+            run("let x = { let a = 3.0 in a + a }; return x;"),
+            Ok(ExprLiteral::NumberLiteral(6.0))
+        );
+        assert!(run("return let a in a;").is_err());
+    }
+
+    #[test]
+    fn test_inter_match_guard() {
+        let sign = "fn sign(x) { let r = \"\"; match (x) { n if n > 0.0 => r = \"positive\"; _ => r = \"other\"; } return r; }";
+        assert_eq!(
+            run(&format!("{} return sign(2.0);", sign)),
+            Ok(ExprLiteral::StringLiteral("positive".to_string()))
+        );
+        assert_eq!(
+            run(&format!("{} return sign(-1.0) + sign(0.0);", sign)),
+            Ok(ExprLiteral::StringLiteral("otherother".to_string()))
+        );
+        // The guard sees the binding of a type pattern, and an arm without a guard still matches.
+        assert_eq!(
+            run("let r = 0.0; match (\"ab\") { string s if s == \"a\" => r = 1.0; string s => r = 2.0; } return r;"),
+            Ok(ExprLiteral::NumberLiteral(2.0))
+        );
+        // A failed guard leaves no binding behind.
+        assert_eq!(
+            run(
+                "let n = 5.0; match (1.0) { n if n > 3.0 => n = 0.0; _ => n = n + 1.0; } return n;"
+            ),
+            Ok(ExprLiteral::NumberLiteral(6.0))
+        );
+        assert_eq!(
+            run("let n = 0.0; match (1.0) { x if nothing => n = 1.0; } return n;"),
+            Err("Undefined variable nothing.".to_string())
+        );
+        assert!(run("match (1.0) { numbr n => print n; }").is_err());
+    }
+
+    #[test]
+    fn test_inter_unset() {
+        assert_eq!(
+            run("let x = 1.0; unset x; return x;"),
+            Err("Undefined variable x.".to_string())
+        );
+        // The shadowed outer x is visible again.
+        assert_eq!(
+            run("let x = 1.0; let r = 0.0; { let x = 2.0; unset x; r = x; } return r;"),
+            Ok(ExprLiteral::NumberLiteral(1.0))
+        );
+        // From an inner scope it removes the outer one.
+        assert_eq!(
+            run("let x = 1.0; { unset x; } return x;"),
+            Err("Undefined variable x.".to_string())
+        );
+        assert_eq!(
+            run("let x = 1.0; unset x; let x = \"again\"; return x;"),
+            Ok(ExprLiteral::StringLiteral("again".to_string()))
+        );
+        assert_eq!(run("unset y;"), Err("Undefined variable y.".to_string()));
+    }
+
+    #[test]
+    fn test_inter_print_keywords() {
+        let sources =
+            "print true; print false; print nil; print \"is \" + to_string(1.0 < 2.0); print to_string(1.0 > 2.0);";
+        assert_eq!(
+            run_printed(sources),
+            (
+                "true\nfalse\nnil\nis true\nfalse\n".to_string(),
+                Ok(ExprLiteral::Nil)
+            )
+        );
+
+        // What is printed scans back to the same value.
+        for value in [ExprLiteral::True, ExprLiteral::False, ExprLiteral::Nil] {
+            assert_eq!(run(&format!("return {};", value.two_string())), Ok(value));
+        }
+    }
+
+    #[test]
+    fn test_inter_chained_assignment() {
+        assert_eq!(
+            run("let a = 0.0; let b = 0.0; let c = a = b = 10.0; return a + b + c;"),
+            Ok(ExprLiteral::NumberLiteral(30.0))
+        );
+        assert_eq!(
+            run("let a = 0.0; let b = 0.0; return a = b = 10.0;"),
+            Ok(ExprLiteral::NumberLiteral(10.0))
+        );
+        assert_eq!(
+            run("let a = 0.0; let b = 0.0; a = (b = 10.0) + 1.0; return a * 100.0 + b;"),
+            Ok(ExprLiteral::NumberLiteral(1110.0))
+        );
+        // Right to left, the inner assignment is done before the outer one reads it.
+        assert_eq!(
+            run("let a = 1.0; let b = 2.0; a = b = a + b; return a * 10.0 + b;"),
+            Ok(ExprLiteral::NumberLiteral(33.0))
+        );
+        assert_eq!(
+            run("let a = 0.0; let b = 0.0; a + b = 10.0;"),
+            Err("Error occurs when assignment at line: 1 at =.".to_string())
+        );
+        assert_eq!(
+            run("let a = 0.0; let b = 0.0; (a) = b = 10.0;"),
+            Err("Error occurs when assignment at line: 1 at =.".to_string())
+        );
+        assert_eq!(
+            run("let a = 0.0; a = c = 10.0;"),
+            Err("Undefined variable c.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_inter_format_currency() {
+        let format = |sources: &str| match run(&format!("return format_currency({});", sources)) {
+            Ok(ExprLiteral::StringLiteral(v)) => v,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(format("1234.56, \"$\""), "$1,234.56");
+        assert_eq!(format("1234567.891, \"$\""), "$1,234,567.89");
+        assert_eq!(format("999.999, \"$\""), "$1,000.00");
+        assert_eq!(format("12.5, \"EUR \""), "EUR 12.50");
+        assert_eq!(format("0.0, \"$\""), "$0.00");
+        assert_eq!(format("-1.0, \"$\""), "-$1.00");
+        assert_eq!(format("-123456.0, \"$\""), "-$123,456.00");
+        assert_eq!(format("-0.001, \"$\""), "$0.00");
+        assert!(run("format_currency(\"1\", \"$\");").is_err());
+        assert!(run("format_currency(1.0, 2.0);").is_err());
+    }
+
+    #[test]
+    fn test_inter_logical_order() {
+        let say = "fn say(text, value) { print text; return value; }";
+        let printed = |sources: &str| run_printed(&format!("{} {}", say, sources));
+        assert_eq!(
+            printed("return say(\"a\", false) or say(\"b\", 1.0);"),
+            ("a\nb\n".to_string(), Ok(ExprLiteral::NumberLiteral(1.0)))
+        );
+        assert_eq!(
+            printed("return say(\"a\", true) or say(\"b\", 1.0);"),
+            ("a\n".to_string(), Ok(ExprLiteral::True))
+        );
+        assert_eq!(
+            printed("return say(\"a\", nil) and say(\"b\", 1.0);"),
+            ("a\n".to_string(), Ok(ExprLiteral::Nil))
+        );
+        assert_eq!(
+            printed("return say(\"a\", 2.0) and say(\"b\", 1.0);"),
+            ("a\nb\n".to_string(), Ok(ExprLiteral::NumberLiteral(1.0)))
+        );
+        // Left to right through a chain, stopping at the first true one.
+        assert_eq!(
+            printed("say(\"a\", false) or say(\"b\", false) and say(\"c\", true) or say(\"d\", true) or say(\"e\", true);").0,
+            "a\nb\nd\n"
+        );
+        // The same inside an assert, which records the operands.
+        assert_eq!(
+            printed("assert say(\"a\", true) or say(\"b\", true);").0,
+            "a\n"
+        );
+    }
+
+    #[test]
+    fn test_inter_fake_clock() {
+        // 2024-02-29 23:59:58 UTC.
+        let clock = FakeClock::new(1709251198.5);
+        let tok = Scanner::new(
+            "print now(); let t = clock(); sleep(2.0); print now(); sleep(86400.0 * 366.0); print now(); return clock() - t;"
+                .to_string(),
+        )
+        .scan_tokens()
+        .unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+        let output = CaptureOutput::new();
+        let value = Interpreter::new()
+            .with_output(Box::new(output.clone()))
+            .with_clock(Box::new(clock.clone()))
+            .interpreter(&pas);
+        assert_eq!(
+            output.out(),
+            "2024-02-29 23:59:58\n2024-03-01 00:00:00\n2025-03-02 00:00:00\n"
+        );
+        assert_eq!(value, Ok(ExprLiteral::NumberLiteral(2.0 + 86400.0 * 366.0)));
+        assert_eq!(clock.now(), 1709251200.5 + 86400.0 * 366.0);
+
+        assert_eq!(format_timestamp(0.0), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(-1.0), "1969-12-31 23:59:59");
+        assert!(run("sleep(-1.0);").is_err());
+        assert!(run("sleep(\"1\");").is_err());
+        let err = run("sleep(100000000000000000000.0);").unwrap_err();
+        assert!(err.contains("sleep expect seconds >= 0 which fit in a Duration, but got"));
+        assert!(err.contains("at line: 1."));
+    }
+
+    #[test]
+    fn test_inter_error_codes() {
+        let code = |sources: &str| {
+            let mut interpreter = Interpreter::new();
+            let _ = run_on(&mut interpreter, sources);
+            interpreter.last_error().map(|e| e.code)
+        };
+        assert_eq!(code("print x;"), Some(ErrorCode::UndefinedVariable));
+        assert_eq!(code("x = 1.0;"), Some(ErrorCode::UndefinedVariable));
+        assert_eq!(code("print 1.0 - \"a\";"), Some(ErrorCode::TypeMismatch));
+        assert_eq!(code("print -\"a\";"), Some(ErrorCode::TypeMismatch));
+        assert_eq!(code("abs(\"a\");"), Some(ErrorCode::TypeMismatch));
+        assert_eq!(code("print 1.0();"), Some(ErrorCode::TypeMismatch));
+        assert_eq!(code("abs(1.0, 2.0);"), Some(ErrorCode::ArityMismatch));
+        assert_eq!(code("assert false;"), Some(ErrorCode::Other));
+        assert_eq!(code("print 1.0;"), None);
+
+        // The code is given where the error occurs, not guessed from the words of the message.
+        assert_eq!(code("abs(\"by zero\");"), Some(ErrorCode::TypeMismatch));
+        assert_eq!(
+            code("abs(\"Undefined variable\");"),
+            Some(ErrorCode::TypeMismatch)
+        );
+        assert_eq!(
+            code("assert_approx(1.0, 2.0, 0.5);"),
+            Some(ErrorCode::Other)
+        );
+
+        // The numbers are given out, they must not change, with or without bignum.
+        let numbers = [
+            ErrorCode::Other,
+            ErrorCode::TypeMismatch,
+            ErrorCode::UndefinedVariable,
+            ErrorCode::DivisionByZero,
+            ErrorCode::ArityMismatch,
+        ]
+        .map(ErrorCode::number);
+        assert_eq!(numbers, [0, 1, 2, 3, 4]);
+
+        // Dividing a number by zero is not an error, it's inf or NaN like in f64.
+        assert_eq!(code("print 1.0 / 0.0;"), None);
+        assert_eq!(
+            run("return 1.0 / 0.0;"),
+            Ok(ExprLiteral::NumberLiteral(f64::INFINITY))
+        );
+
+        let mut interpreter = Interpreter::new();
+        let _ = run_on(&mut interpreter, "let a = 1.0;\nprint a - \"b\";");
+        assert!(interpreter
+            .last_error()
+            .unwrap()
+            .to_string()
+            .starts_with("E001: Error occur when interpreter at line 2"));
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_inter_error_code_bigint_division() {
+        let mut interpreter = Interpreter::new();
+        let _ = run_on(&mut interpreter, "print 1n / 0n;");
+        let error = interpreter.last_error().unwrap();
+        assert_eq!(error.code, ErrorCode::DivisionByZero);
+        assert_eq!(
+            error.to_string(),
+            "E003: Error occur when interpreter at line 1 at / for dividing BigInt by zero."
+        );
+    }
+
+    #[test]
+    fn test_inter_when() {
+        let grade = "fn grade(x) = when { x >= 90.0 => \"A\", x >= 80.0 => \"B\", else => \"C\" };";
+        assert_eq!(
+            run(&format!(
+                "{} return grade(95.0) + grade(85.0) + grade(10.0);",
+                grade
+            )),
+            Ok(ExprLiteral::StringLiteral("ABC".to_string()))
+        );
+        assert_eq!(
+            run("let x = when { false => 1.0, nil => 2.0 }; return x;"),
+            Ok(ExprLiteral::Nil)
+        );
+
+        // Only the conditions up to the true one are evaluated.
+        let sources =
+            "fn say(text, value) { print text; return value; } print when { say(\"a\", false) => 1.0, say(\"b\", true) => say(\"B\", 2.0), say(\"c\", true) => 3.0, else => say(\"e\", 4.0), };";
+        assert_eq!(
+            run_printed(sources),
+            ("a\nb\nB\n2\n".to_string(), Ok(ExprLiteral::Nil))
+        );
+
+        assert!(run("return when { else => 1.0, true => 2.0 };").is_err());
+        assert!(run("return when { true => 1.0 false => 2.0 };").is_err());
+    }
+
+    #[test]
+    fn test_inter_watch() {
+        let tok = Scanner::new(
+            "let i = 0.0; let total = 0.0; while (i < 3.0) { i = i + 1.0; total = total + i; } fn reset() { i = nil; } reset(); { let i = \"inner\"; i = \"x\"; }"
+                .to_string(),
+        )
+        .scan_tokens()
+        .unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+
+        let changes = Rc::new(RefCell::new(vec![]));
+        let seen = changes.clone();
+        let mut interpreter = Interpreter::new();
+        interpreter.watch("i", move |old, new| {
+            seen.borrow_mut()
+                .push(format!("{} -> {}", old.two_string(), new.two_string()))
+        });
+        interpreter.interpreter(&pas).unwrap();
+        assert_eq!(
+            *changes.borrow(),
+            vec!["0 -> 1", "1 -> 2", "2 -> 3", "3 -> nil", "inner -> x"]
+        );
+    }
+
+    #[test]
+    fn test_inter_repeat() {
+        assert_eq!(run_printed("repeat 3.0 { print \"x\"; }").0, "x\nx\nx\n");
+        // The count is evaluated once.
+        assert_eq!(
+            run_printed("let n = 2.0; repeat n { n = n + 1.0; print n; }"),
+            ("3\n4\n".to_string(), Ok(ExprLiteral::Nil))
+        );
+        assert_eq!(run_printed("repeat 0.0 { print \"x\"; }").0, "");
+        assert_eq!(
+            run("let i = 0.0; 'outer: repeat 5.0 { i = i + 1.0; if (i == 2.0) { break 'outer; } } return i;"),
+            Ok(ExprLiteral::NumberLiteral(2.0))
+        );
+        assert_eq!(
+            run("fn f() { repeat 5.0 { return 7.0; } } return f();"),
+            Ok(ExprLiteral::NumberLiteral(7.0))
+        );
+        assert_eq!(
+            run("print 1.0;\nrepeat -1.0 { print \"x\"; }"),
+            Err("Error occur when interpreter at line 2 at repeat for a count which is not a whole number >= 0, got -1.".to_string())
+        );
+        assert!(run("repeat 1.5 { }").is_err());
+        assert!(run("repeat \"3\" { }").is_err());
+        assert!(run("repeat 3.0 print 1.0;").is_err());
+    }
+
+    #[test]
+    fn test_inter_trace() {
+        let sources =
+            "fn double(x) {\n  return x * 2.0;\n}\nlet a = 1.0;\na = double(a);\nprint a;";
+        let tok = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+        let mut interpreter = Interpreter::new()
+            .with_output(Box::new(CaptureOutput::new()))
+            .with_trace(true);
+        interpreter.interpreter(&pas).unwrap();
+
+        let trace: Vec<String> = interpreter
+            .trace()
+            .unwrap()
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            trace,
+            vec![
+                "line 1: statement",
+                "line 4: statement",
+                "line 4: let a = \"1\"",
+                "line 5: statement",
+                "line 5: call double",
+                "line 2: statement",
+                "line 5: a = \"2\"",
+                "line 6: statement",
+                "line 6: print \"2\\n\"",
+            ]
+        );
+        assert_eq!(
+            interpreter.trace().unwrap()[4],
+            TraceEvent::Call {
+                line: 5,
+                name: "double".to_string()
+            }
+        );
+
+        // Written out one per line, the events are read back as they were.
+        for event in interpreter.trace().unwrap() {
+            assert_eq!(event.to_string().parse::<TraceEvent>().as_ref(), Ok(event));
+        }
+
+        // Off by default, and a new run starts a new trace.
+        assert_eq!(Interpreter::new().trace(), None);
+        interpreter.interpreter(&pas[3..].to_vec()).unwrap();
+        assert_eq!(
+            interpreter.trace().unwrap().first(),
+            Some(&TraceEvent::Statement { line: Some(6) })
+        );
+    }
 }
 
 // cargo test unique-keyword -- --nocapture
@@ -0,0 +1,53 @@
+use std::{cell::RefCell, rc::Rc};
+
+// Where the Interpreter writes to, so that the output can be captured instead of going to the terminal.
+pub trait Output {
+    fn print(&mut self, text: &str); // normal output, like print statement.
+
+    fn eprint(&mut self, text: &str); // diagnostics, like debug statement.
+}
+
+// Write to stdout and stderr.
+#[derive(Debug, Default)]
+pub struct StdOutput;
+
+impl Output for StdOutput {
+    fn print(&mut self, text: &str) {
+        print!("{}", text);
+    }
+
+    fn eprint(&mut self, text: &str) {
+        eprint!("{}", text);
+    }
+}
+
+// Keep everything in memory, clones share the same buffers so the caller can read them afterwards.
+#[derive(Debug, Default, Clone)]
+pub struct CaptureOutput {
+    out: Rc<RefCell<String>>,
+    err: Rc<RefCell<String>>,
+}
+
+impl CaptureOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn out(&self) -> String {
+        self.out.borrow().clone()
+    }
+
+    pub fn err(&self) -> String {
+        self.err.borrow().clone()
+    }
+}
+
+impl Output for CaptureOutput {
+    fn print(&mut self, text: &str) {
+        self.out.borrow_mut().push_str(text);
+    }
+
+    fn eprint(&mut self, text: &str) {
+        self.err.borrow_mut().push_str(text);
+    }
+}
@@ -1,20 +1,115 @@
+use std::collections::HashMap;
+
 use super::{
+    callable::MyOperator,
+    environment::DISCARD,
     expr::{Expr, ExprLiteral},
-    stmt::Stmt,
+    stmt::{MatchArm, Pattern, Stmt},
     token::{LiterialValue, Token, TokenType},
 };
 
 pub struct Parser {
-    tokens: Vec<Token>, //
-    current: usize,     // num to index when parse Vec<Token>
+    tokens: Vec<Token>,               //
+    current: usize,                   // num to index when parse Vec<Token>
+    strict_blocks: bool,              // if / while / for bodies must be "{ }" blocks.
+    condition_assign: ConditionCheck, // what to do with `if (x = 1.0)`.
+    warnings: Vec<String>,            // diagnostics which don't stop parsing.
+    docs: HashMap<usize, String>,     // doc comments, by the index of the token after them.
+    labels: Vec<String>,              // labels of the enclosing statements which break can leave.
+}
+
+// How to treat an assignment used directly as the condition of if / while.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConditionCheck {
+    Allow,
+    Warn,
+    Error,
+}
+
+#[allow(dead_code)] // Right is for operators like a power, there is none yet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Associativity {
+    Left,  // a - b - c is ( a - b ) - c.
+    Right, // a op b op c is a op ( b op c ).
+}
+
+// Binary operators with their precedence, a bigger one binds tighter. A new operator needs a
+// line here and an arm in Interpreter::binary_operation().
+const BINARY_OPERATORS: &[(TokenType, u8, Associativity)] = &[
+    (TokenType::BangEqual, 1, Associativity::Left),
+    (TokenType::EqualEqual, 1, Associativity::Left),
+    (TokenType::Greater, 2, Associativity::Left),
+    (TokenType::GreaterEqual, 2, Associativity::Left),
+    (TokenType::Less, 2, Associativity::Left),
+    (TokenType::LessEqual, 2, Associativity::Left),
+    (TokenType::Minus, 3, Associativity::Left),
+    (TokenType::Plus, 3, Associativity::Left),
+    (TokenType::Slash, 4, Associativity::Left),
+    (TokenType::Star, 4, Associativity::Left),
+];
+
+fn binary_operator(token_type: &TokenType) -> Option<(u8, Associativity)> {
+    BINARY_OPERATORS
+        .iter()
+        .find(|(t, ..)| t == token_type)
+        .map(|(_, precedence, associativity)| (*precedence, *associativity))
 }
 
 impl Parser {
     // brief: Create a Parser with Token vector , and set self.current to 0.
     // input:
     // output:
+    //        Doc comments are taken out of the tokens here, so that they can appear anywhere.
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        let mut docs: HashMap<usize, String> = HashMap::new();
+        let mut rest = vec![];
+        for token in tokens {
+            if token.token_type != TokenType::Doc {
+                rest.push(token);
+                continue;
+            }
+            if let Some(LiterialValue::StringValue(text)) = token.literial {
+                docs.entry(rest.len())
+                    .and_modify(|doc| {
+                        doc.push('\n');
+                        doc.push_str(&text);
+                    })
+                    .or_insert(text);
+            }
+        }
+        Self {
+            tokens: rest,
+            current: 0,
+            strict_blocks: false,
+            condition_assign: ConditionCheck::Allow,
+            warnings: vec![],
+            docs,
+            labels: vec![],
+        }
+    }
+
+    // brief: Strict mode rejects single-statement bodies like `if (a) print a;`, default is permissive.
+    // input:
+    // output:
+    pub fn with_strict_blocks(mut self, strict: bool) -> Self {
+        self.strict_blocks = strict;
+        self
+    }
+
+    // brief: Flag `if (x = 1.0)` which is likely a typo of "==", default is Allow.
+    //        Wrapping it in another "( )" like `if ((x = 1.0))` marks it as intentional.
+    // input:
+    // output:
+    pub fn with_condition_assign(mut self, check: ConditionCheck) -> Self {
+        self.condition_assign = check;
+        self
+    }
+
+    // brief: Warnings collected by parse().
+    // input:
+    // output:
+    pub fn warnings(&self) -> &Vec<String> {
+        &self.warnings
     }
 
     /*
@@ -22,43 +117,60 @@ impl Parser {
 
     declaration -> letDecl | statement | funDecl
 
-    funDecl -> "fn" function
+    funDecl -> "@memo" ? "fn" function
 
-    function -> Identifier "(" parameters ? ")" block
+    function -> Identifier "(" parameters ? ")" ( block | "=" expression ";" )
 
     parameters -> Identifier ("," Identifier ) *
 
     letDecl -> "let" Identifier ( "=" expression ) ? ";"
 
-    statement -> exprStmt | printStmt | block | ifStmt | whileStmt | returnStmt
+    statement -> exprStmt | printStmt | block | ifStmt | whileStmt | returnStmt | assertStmt | debugStmt | matchStmt | labeledStmt | breakStmt | unsetStmt | repeatStmt
+
+    labeledStmt -> Label ":" ( block | whileStmt | forStmt | repeatStmt )
+
+    repeatStmt -> "repeat" expression block    ( the count is evaluated once )
+
+    unsetStmt -> "unset" Identifier ";"    ( an outer variable of the same name is visible again )
+
+    breakStmt -> "break" Label ";"    ( only inside the statement with that label, not across fn )
+
+    matchStmt -> "match" "(" expression ")" "{" ( pattern ( "if" expression ) ? "=>" statement ) * "}"
+
+    pattern -> "_" | literal | Identifier | ( "number" | "string" | "bool" | "nil" | "function" ) Identifier ?
 
     returnStmt -> return expression ? ";"
 
+    assertStmt -> "assert" expression ";"
+
+    debugStmt -> "debug" ";"
+
     whileStmt -> "while" "(" expression ")" statement
 
     ifStmt -> "if" "(" expression ")" statement ("else" statement ) ?
 
-    block -> "{" declaration "}"
+    block -> "{" declaration "}"    ( the ";" of the last statement may be left out )
 
     exprStmt -> expression ";"
 
-    printstmt -> "print" expression ";"
+    printstmt -> "print" expression ( "," expression ) * ( "sep" expression ) ? ( "end" expression ) ? ";"
 
     expression -> assignment
 
-    assignment -> Identifier "=" assignment | logic_or
-
-    logic_or -> logic_and ( "or" logic_and) *
+    assignment -> Identifier "=" assignment | pipe
 
-    logic_and -> equality ( "and" equality) *
+    pipe -> logic_or ( "|>" logic_or ) *
 
-    equality -> comparision ( ("!=" | "==") comparision  ) *
+    logic_or -> logic_and ( "or" logic_and) *
 
-    comparision -> term ( ( ">" | ">=" | "<" | "<=") term ) *
+    logic_and -> binary ( "and" binary) *
 
-    term -> factor ( ( "-" | "+" ) factor ) *
+    binary -> unary ( operator unary ) *    ( by the table BINARY_OPERATORS, from loose to tight: )
 
-    factor -> unary ( ( "/" | "*") unary ) *
+        equality    ( "!=" | "==" )
+        comparision ( ">" | ">=" | "<" | "<=" )
+        term        ( "-" | "+" )
+        factor      ( "/" | "*" )    ( all of them are left-associative )
 
     unary -> ( ( "!" | "-" ) unary ) | call
 
@@ -66,7 +178,21 @@ impl Parser {
 
     arguments -> expression ( "," expression ) *
 
-    primary -> NUMBER | STRING | "true" | "false" | "nil" | "(" expression ")" | Identifier
+    ( "_" can be a let name, a parameter or assigned to, but never read )
+
+    primary -> NUMBER | STRING + | "true" | "false" | "nil" | "(" expression ")" | Identifier | ifExpr | blockExpr | operatorValue | lazyExpr | letInExpr | whenExpr
+
+    whenExpr -> "when" "{" ( expression "=>" expression "," ) * ( "else" "=>" expression ) ? "}"    ( the first true condition gives the value, nil if none )
+
+    letInExpr -> "let" Identifier "=" expression "in" expression    ( also as a statement, then nothing is declared )
+
+    lazyExpr -> "lazy" expression    ( evaluated at most once, when a variable or parameter holding it is read, or right away anywhere else )
+
+    operatorValue -> "(" operator ")"    ( any operator of BINARY_OPERATORS, a function of two arguments )
+
+    ifExpr -> "if" "(" expression ")" blockExpr ( "else" ( ifExpr | blockExpr ) ) ?
+
+    blockExpr -> "{" declaration * expression ? "}"    ( the value of the block is the last expression without ";" )
     */
 
     // brief: Pub function used to Parse a Vec<Stmt>.
@@ -85,10 +211,24 @@ impl Parser {
     // input:
     // output:
     fn declaration(&mut self) -> Result<Stmt, String> {
+        let doc = self.docs.remove(&self.current); // only kept by let and fn.
+
         if self.match_tokens(&[TokenType::Let]) {
             match self.let_declaration() {
                 Ok(v) => {
-                    return Ok(v);
+                    return Ok(Self::attach_doc(v, doc));
+                }
+                Err(err) => {
+                    self.synchronize();
+                    return Err(err);
+                }
+            }
+        }
+
+        if self.match_tokens(&[TokenType::At]) {
+            match self.annotated_function() {
+                Ok(v) => {
+                    return Ok(Self::attach_doc(v, doc));
                 }
                 Err(err) => {
                     self.synchronize();
@@ -100,7 +240,7 @@ impl Parser {
         if self.match_tokens(&[TokenType::Fn]) {
             match self.function("function".to_string()) {
                 Ok(v) => {
-                    return Ok(v);
+                    return Ok(Self::attach_doc(v, doc));
                 }
                 Err(err) => {
                     self.synchronize();
@@ -117,10 +257,43 @@ impl Parser {
         }
     }
 
-    // brief: function -> Identifier "(" parameters ? ")" block
+    // brief: Put the doc comment before a let / fn into it.
+    // input:
+    // output:
+    fn attach_doc(mut statement: Stmt, doc: Option<String>) -> Stmt {
+        match &mut statement {
+            Stmt::Let { doc: v, .. } | Stmt::Function { doc: v, .. } => *v = doc,
+            _ => {}
+        }
+        statement
+    }
+
+    // brief: funDecl -> "@memo" "fn" function, the only annotation for now.
+    //        Results of a @memo function are cached by argument values, so it should be pure:
+    //        side effects like print only happen on the first call with the same arguments.
     // input:
     // output:
-    fn function(&mut self, kind: String) -> Result<Stmt, String> {
+    fn annotated_function(&mut self) -> Result<Stmt, String> {
+        let annotation = self.consume(TokenType::Identifier)?;
+        if annotation.lexeme != "memo" {
+            return Err(format!(
+                "Unknown annotation @{} at line: {}.",
+                annotation.lexeme, annotation.line_number
+            ));
+        }
+        self.consume(TokenType::Fn)?;
+
+        let mut function = self.function("function".to_string())?;
+        if let Stmt::Function { memo, .. } = &mut function {
+            *memo = true;
+        }
+        Ok(function)
+    }
+
+    // brief: function -> Identifier "(" parameters ? ")" ( block | "=" expression ";" )
+    // input:
+    // output:
+    fn function(&mut self, _kind: String) -> Result<Stmt, String> {
         let name = self.consume(TokenType::Identifier)?;
         self.consume(TokenType::LeftParen)?;
 
@@ -143,11 +316,38 @@ impl Parser {
             ));
         }
 
-        self.consume(TokenType::LeftBrace)?;
+        // fn square(x) = x * x; is the same as fn square(x) { return x * x; }
+        if self.match_tokens(&[TokenType::Equal]) {
+            let keyword = self.previous();
+            let value = self.expression()?;
+            self.consume_terminator()?;
+
+            let body = Box::new(Stmt::Block {
+                statements: vec![Stmt::Return { keyword, value }],
+            });
+            return Ok(Stmt::Function {
+                name,
+                params,
+                body,
+                memo: false,
+                doc: None,
+            });
+        }
 
-        let body = Box::new(self.block()?);
+        self.consume(TokenType::LeftBrace)?;
 
-        Ok(Stmt::Function { name, params, body })
+        let labels = std::mem::take(&mut self.labels); // break can't leave the function.
+        let body = self.block();
+        self.labels = labels;
+        let body = Box::new(body?);
+
+        Ok(Stmt::Function {
+            name,
+            params,
+            body,
+            memo: false,
+            doc: None,
+        })
     }
 
     // parameters -> Identifier ("," Identifier ) *
@@ -163,13 +363,27 @@ impl Parser {
         };
         if self.match_tokens(&[TokenType::Equal]) {
             initializer = self.expression()?;
+            // `let a = 2.0 in a * a;` is an expression statement, a is not declared.
+            if self.match_tokens(&[TokenType::In]) {
+                let expr = Expr::LetIn {
+                    name,
+                    initializer: Box::new(initializer),
+                    body: Box::new(self.expression()?),
+                };
+                self.consume_terminator()?;
+                return Ok(Stmt::Expression(expr));
+            }
         }
-        let _ = self.consume(TokenType::Semicolon)?;
+        self.consume_terminator()?;
 
-        Ok(Stmt::Let { name, initializer })
+        Ok(Stmt::Let {
+            name,
+            initializer,
+            doc: None,
+        })
     }
 
-    // brief: statement -> exprStmt | printStmt | block | ifStmt | whileStmt | returnStmt
+    // brief: statement -> exprStmt | printStmt | block | ifStmt | whileStmt | returnStmt | assertStmt | debugStmt
     // input:
     // output:
     fn statement(&mut self) -> Result<Stmt, String> {
@@ -185,6 +399,33 @@ impl Parser {
             self.for_statement() // Syntactic sugar.
         } else if self.match_tokens(&[TokenType::Return]) {
             self.return_statement()
+        } else if self.match_tokens(&[TokenType::Assert]) {
+            self.assert_statement()
+        } else if self.match_tokens(&[TokenType::Debug]) {
+            let keyword = self.previous();
+            self.consume_terminator()?;
+            Ok(Stmt::Debug { keyword })
+        } else if self.match_tokens(&[TokenType::Match]) {
+            self.match_statement()
+        } else if self.match_tokens(&[TokenType::Label]) {
+            self.labeled_statement()
+        } else if self.match_tokens(&[TokenType::Break]) {
+            self.break_statement()
+        } else if self.match_tokens(&[TokenType::Repeat]) {
+            let keyword = self.previous();
+            let count = self.expression()?;
+            self.consume(TokenType::LeftBrace)?;
+            let body = Box::new(self.block()?);
+            Ok(Stmt::Repeat {
+                keyword,
+                count,
+                body,
+            })
+        } else if self.match_tokens(&[TokenType::Unset]) {
+            let keyword = self.previous();
+            let name = self.consume(TokenType::Identifier)?;
+            self.consume_terminator()?;
+            Ok(Stmt::Unset { keyword, name })
         } else {
             self.expression_statement()
         }
@@ -198,14 +439,163 @@ impl Parser {
         let mut value = Expr::Literal {
             value: ExprLiteral::Nil,
         };
-        if !self.check(TokenType::Semicolon) {
+        if !self.check(TokenType::Semicolon) && !self.check(TokenType::RightBrace) {
             value = self.expression()?;
         }
-        self.consume(TokenType::Semicolon)?;
+        self.consume_terminator()?;
 
         Ok(Stmt::Return { keyword, value })
     }
 
+    // brief: labeledStmt -> Label ":" ( block | whileStmt | forStmt | repeatStmt )
+    // input:
+    // output:
+    fn labeled_statement(&mut self) -> Result<Stmt, String> {
+        let label = self.previous();
+        self.consume(TokenType::Colon)?;
+        if !(self.check(TokenType::LeftBrace)
+            || self.check(TokenType::While)
+            || self.check(TokenType::For)
+            || self.check(TokenType::Repeat))
+        {
+            return Err(format!(
+                "Parsering error occur, a label can only be put before a block or a loop at line: {} in {}.",
+                label.line_number, label.lexeme
+            ));
+        }
+        if self.labels.contains(&label.lexeme) {
+            return Err(format!(
+                "Parsering error occur, label {} is already used by an enclosing statement at line: {}.",
+                label.lexeme, label.line_number
+            ));
+        }
+
+        self.labels.push(label.lexeme.clone());
+        let body = self.statement();
+        self.labels.pop();
+
+        Ok(Stmt::Labeled {
+            label,
+            body: Box::new(body?),
+        })
+    }
+
+    // brief: breakStmt -> "break" Label ";"
+    // input:
+    // output:
+    fn break_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let label = self.consume(TokenType::Label)?;
+        if !self.labels.contains(&label.lexeme) {
+            return Err(format!(
+                "Parsering error occur, break to undefined label {} at line: {}.",
+                label.lexeme, label.line_number
+            ));
+        }
+        self.consume_terminator()?;
+        Ok(Stmt::Break { keyword, label })
+    }
+
+    // brief: matchStmt -> "match" "(" expression ")" "{" ( pattern ( "if" expression ) ? "=>" statement ) * "}"
+    // input:
+    // output:
+    fn match_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftParen)?;
+        let value = self.expression()?;
+        self.consume(TokenType::RightParen)?;
+        self.consume(TokenType::LeftBrace)?;
+
+        let mut arms = vec![];
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let pattern = self.pattern()?;
+            let guard = if self.match_tokens(&[TokenType::If]) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            self.consume(TokenType::FatArrow)?;
+            let body = self.body_statement()?;
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+        }
+        self.consume(TokenType::RightBrace)?;
+
+        Ok(Stmt::Match {
+            keyword,
+            value,
+            arms,
+        })
+    }
+
+    // brief: pattern -> "_" | literal | Identifier | ( "number" | "string" | "bool" | "nil" | "function" ) Identifier ?
+    //        "nil" alone is the literal, "nil x" is the type pattern. Any other name alone binds the value.
+    // input:
+    // output:
+    fn pattern(&mut self) -> Result<Pattern, String> {
+        if self.check(TokenType::Nil) && self.check_next(TokenType::Identifier) {
+            let type_name = self.advance();
+            let binding = Some(self.advance());
+            return Ok(Pattern::Type { type_name, binding });
+        }
+        if self.match_tokens(&[TokenType::Identifier]) {
+            let name = self.previous();
+            if name.lexeme == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+            let mut type_names = vec!["number", "string", "bool", "function"];
+            if cfg!(feature = "bignum") {
+                type_names.push("bigint");
+            }
+            let is_type = type_names.contains(&name.lexeme.as_str());
+            if !is_type && !self.check(TokenType::Identifier) {
+                return Ok(Pattern::Binding(name));
+            }
+            if !is_type {
+                return Err(format!(
+                    "Unknown type {} in pattern at line: {}.",
+                    name.lexeme, name.line_number
+                ));
+            }
+            let binding = if self.match_tokens(&[TokenType::Identifier]) {
+                Some(self.previous())
+            } else {
+                None
+            };
+            return Ok(Pattern::Type {
+                type_name: name,
+                binding,
+            });
+        }
+
+        let negative = self.match_tokens(&[TokenType::Minus]);
+        match self.primary()? {
+            Expr::Literal {
+                value: ExprLiteral::NumberLiteral(v),
+            } if negative => Ok(Pattern::Literal(ExprLiteral::NumberLiteral(-v))),
+            Expr::Literal { value } if !negative => Ok(Pattern::Literal(value)),
+            _ => Err(format!(
+                "Parsering error occur, expected a pattern at line: {} in {}.",
+                self.previous().line_number,
+                self.previous().lexeme
+            )),
+        }
+    }
+
+    // brief: assertStmt -> "assert" expression ";"
+    // input:
+    // output:
+    fn assert_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let condition = self.expression()?;
+        self.consume_terminator()?;
+
+        Ok(Stmt::Assert { keyword, condition })
+    }
+
     // for ( initializer condition increment ) body
     // -----------------Syntactic sugar----------------------
     // { initializer while ( condition ) { body increment } }
@@ -242,11 +632,11 @@ impl Parser {
 
         self.consume(TokenType::RightParen)?;
 
-        let mut body = self.statement()?;
+        let mut body = self.body_statement()?;
 
-        if increment.is_some() {
+        if let Some(increment) = increment {
             body = Stmt::Block {
-                statements: vec![body, Stmt::Expression(increment.unwrap())],
+                statements: vec![body, Stmt::Expression(increment)],
             }
         }
 
@@ -255,9 +645,9 @@ impl Parser {
             body: Box::new(body),
         };
 
-        if initializer.is_some() {
+        if let Some(initializer) = initializer {
             body = Stmt::Block {
-                statements: vec![initializer.unwrap(), body],
+                statements: vec![initializer, body],
             }
         }
 
@@ -270,11 +660,11 @@ impl Parser {
     fn while_statement(&mut self) -> Result<Stmt, String> {
         self.consume(TokenType::LeftParen)?;
 
-        let condition = self.expression()?;
+        let condition = self.condition()?;
 
         self.consume(TokenType::RightParen)?;
 
-        let body = Box::new(self.statement()?);
+        let body = Box::new(self.body_statement()?);
 
         Ok(Stmt::While { condition, body })
     }
@@ -285,16 +675,21 @@ impl Parser {
     fn if_statement(&mut self) -> Result<Stmt, String> {
         self.consume(TokenType::LeftParen)?;
 
-        let condition = self.expression()?;
+        let condition = self.condition()?;
 
         self.consume(TokenType::RightParen)?;
 
-        let then_branch = Box::new(self.statement()?);
+        let then_branch = Box::new(self.body_statement()?);
 
         let mut else_branch = None;
 
         if self.match_tokens(&[TokenType::Else]) {
-            else_branch = Some(Box::new(self.statement()?));
+            // "else if" is still allowed in strict mode.
+            if self.match_tokens(&[TokenType::If]) {
+                else_branch = Some(Box::new(self.if_statement()?));
+            } else {
+                else_branch = Some(Box::new(self.body_statement()?));
+            }
         }
 
         Ok(Stmt::If {
@@ -304,15 +699,69 @@ impl Parser {
         })
     }
 
-    // brief: printstmt -> "print" expression ";"
+    // brief: Condition of if / while, which checks `if (x = 1.0)` according to condition_assign.
+    // input:
+    // output:
+    fn condition(&mut self) -> Result<Expr, String> {
+        let condition = self.expression()?;
+        if let Expr::Assign { name, .. } = &condition {
+            let message = format!(
+                "Assignment used as condition at line: {} in {}, use \"==\" to compare or \"(( ))\" if intended.",
+                name.line_number, name.lexeme
+            );
+            match self.condition_assign {
+                ConditionCheck::Allow => {}
+                ConditionCheck::Warn => self.warnings.push(message),
+                ConditionCheck::Error => return Err(message),
+            }
+        }
+        Ok(condition)
+    }
+
+    // brief: Body of if / while / for, which has to be a block in strict mode.
+    // input:
+    // output:
+    fn body_statement(&mut self) -> Result<Stmt, String> {
+        if self.strict_blocks && !self.check(TokenType::LeftBrace) {
+            return Err(format!(
+                "Parsering error occur, expected '{{' before the body at line: {} in {}.",
+                self.peek().line_number,
+                self.peek().lexeme,
+            ));
+        }
+        self.statement()
+    }
+
+    // brief: printstmt -> "print" expression ( "," expression ) * ( "sep" expression ) ? ( "end" expression ) ? ";"
+    //        "sep" and "end" are only special right after the printed values, they are not keywords.
     // input:
     // output:
     fn print_statement(&mut self) -> Result<Stmt, String> {
-        let expr = self.expression()?;
+        let keyword = self.previous();
+        let mut values = vec![self.expression()?];
+        while self.match_tokens(&[TokenType::Comma]) {
+            values.push(self.expression()?);
+        }
 
-        self.consume(TokenType::Semicolon)?;
+        let mut sep = None;
+        if self.check(TokenType::Identifier) && self.peek().lexeme == "sep" {
+            self.advance();
+            sep = Some(self.expression()?);
+        }
+        let mut end = None;
+        if self.check(TokenType::Identifier) && self.peek().lexeme == "end" {
+            self.advance();
+            end = Some(self.expression()?);
+        }
+
+        self.consume_terminator()?;
 
-        Ok(Stmt::Print(expr))
+        Ok(Stmt::Print {
+            keyword,
+            values,
+            sep,
+            end,
+        })
     }
 
     // brief: exprStmt -> expression ";"
@@ -321,7 +770,7 @@ impl Parser {
     fn expression_statement(&mut self) -> Result<Stmt, String> {
         let expr = self.expression()?;
 
-        self.consume(TokenType::Semicolon)?;
+        self.consume_terminator()?;
 
         Ok(Stmt::Expression(expr))
     }
@@ -340,6 +789,73 @@ impl Parser {
         Ok(Stmt::Block { statements })
     }
 
+    // brief: ifExpr -> "if" "(" expression ")" blockExpr ( "else" ( ifExpr | blockExpr ) ) ?
+    //        Only in expression position like `let x = if (a) { 1.0 } else { 2.0 };`, the branches
+    //        have to be blocks, and a missing else gives nil.
+    // input:
+    // output:
+    fn if_expression(&mut self) -> Result<Expr, String> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftParen)?;
+        let condition = Box::new(self.condition()?);
+        self.consume(TokenType::RightParen)?;
+
+        self.consume(TokenType::LeftBrace)?;
+        let then_branch = Box::new(self.block_expression()?);
+
+        let mut else_branch = None;
+        if self.match_tokens(&[TokenType::Else]) {
+            if self.match_tokens(&[TokenType::If]) {
+                else_branch = Some(Box::new(self.if_expression()?));
+            } else {
+                self.consume(TokenType::LeftBrace)?;
+                else_branch = Some(Box::new(self.block_expression()?));
+            }
+        }
+
+        Ok(Expr::If {
+            keyword,
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    // brief: blockExpr -> "{" declaration * expression ? "}", the "{" is consumed already.
+    // input:
+    // output:
+    fn block_expression(&mut self) -> Result<Expr, String> {
+        let labels = std::mem::take(&mut self.labels); // break can't leave an expression.
+        let result = self.block_expression_body();
+        self.labels = labels;
+        result
+    }
+
+    fn block_expression_body(&mut self) -> Result<Expr, String> {
+        let brace = self.previous();
+        let mut statements = vec![];
+        let mut value = None;
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let statement = self.declaration()?;
+            if let Stmt::Expression(expr) = &statement {
+                // `{ a; b }` gives b, while `{ a; b; }` gives nil.
+                if self.check(TokenType::RightBrace)
+                    && self.previous().token_type != TokenType::Semicolon
+                {
+                    value = Some(Box::new(expr.clone()));
+                    break;
+                }
+            }
+            statements.push(statement);
+        }
+        self.consume(TokenType::RightBrace)?;
+        Ok(Expr::Block {
+            brace,
+            statements,
+            value,
+        })
+    }
+
     // brief: expression -> assignment
     // input:
     // output:
@@ -347,11 +863,11 @@ impl Parser {
         self.assignment()
     }
 
-    // brief: assignment -> Identifier "=" assignment | logic_or
+    // brief: assignment -> Identifier "=" assignment | pipe
     // input:
     // output:
     fn assignment(&mut self) -> Result<Expr, String> {
-        let expr = self.logic_or()?;
+        let expr = self.pipe()?;
         if self.match_tokens(&[TokenType::Equal]) {
             let equals = self.previous();
             let value = self.assignment()?;
@@ -370,80 +886,43 @@ impl Parser {
         Ok(expr)
     }
 
-    // brief: logic_or -> logic_and ( "or" logic_and) *
+    // brief: pipe -> logic_or ( "|>" logic_or ) *
+    //        x |> f |> g is lowered to g(f(x)).
     // input:
     // output:
-    fn logic_or(&mut self) -> Result<Expr, String> {
-        let mut expr = self.logic_and()?;
-
-        while self.match_tokens(&[TokenType::Or]) {
-            let operator = self.previous();
-            let right_expr = self.logic_and()?;
+    fn pipe(&mut self) -> Result<Expr, String> {
+        let mut expr = self.logic_or()?;
 
-            expr = Expr::Logical {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right_expr),
-            };
-        }
-        Ok(expr)
-    }
+        while self.match_tokens(&[TokenType::PipeArrow]) {
+            let paren = self.previous();
+            let callee = self.logic_or()?;
 
-    // brief: logic_and -> equality ( "and" equality) *
-    // input:
-    // output:
-    fn logic_and(&mut self) -> Result<Expr, String> {
-        let mut expr = self.equality()?;
-
-        while self.match_tokens(&[TokenType::And]) {
-            let operator = self.previous();
-            let right_expr = self.equality()?;
-
-            expr = Expr::Logical {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right_expr),
+            if let Expr::Literal { .. } = callee {
+                return Err(format!(
+                    "Error occurs when piping into a literal at line: {} at {}.",
+                    paren.line_number, paren.lexeme
+                ));
             }
-        }
-        Ok(expr)
-    }
-
-    // brief: equality -> comparision ( ("!=" | "==") comparision  ) *
-    // input:
-    // output:
-    fn equality(&mut self) -> Result<Expr, String> {
-        let mut expr = self.comparision()?;
-
-        while self.match_tokens(&[TokenType::BangEqual, TokenType::EqualEqual]) {
-            let operator = self.previous();
-            let right_expr = self.comparision()?;
-
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right_expr),
+            expr = Expr::Call {
+                callee: Box::new(callee),
+                paren,
+                arguments: vec![expr],
             };
         }
-
         Ok(expr)
     }
 
-    // brief: comparision -> term ( ( ">" | ">=" | "<" | "<=") term ) *
+    // brief: logic_or -> logic_and ( "or" logic_and) *
     // input:
     // output:
-    fn comparision(&mut self) -> Result<Expr, String> {
-        let mut expr = self.term()?;
+    fn logic_or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.logic_and()?;
 
-        while self.match_tokens(&[
-            TokenType::Greater,
-            TokenType::GreaterEqual,
-            TokenType::Less,
-            TokenType::LessEqual,
-        ]) {
+        while self.match_tokens(&[TokenType::Or]) {
             let operator = self.previous();
-            let right_expr = self.term()?;
+            let right_expr = self.logic_and()?;
 
-            expr = Expr::Binary {
+            expr = Expr::Logical {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right_expr),
@@ -452,34 +931,41 @@ impl Parser {
         Ok(expr)
     }
 
-    // brief: term -> factor ( ( "-" | "+" ) factor ) *
+    // brief: logic_and -> binary ( "and" binary) *
     // input:
     // output:
-    fn term(&mut self) -> Result<Expr, String> {
-        let mut expr = self.factor()?;
+    fn logic_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.binary(0)?;
 
-        while self.match_tokens(&[TokenType::Minus, TokenType::Plus]) {
+        while self.match_tokens(&[TokenType::And]) {
             let operator = self.previous();
-            let right_expr = self.factor()?;
+            let right_expr = self.binary(0)?;
 
-            expr = Expr::Binary {
+            expr = Expr::Logical {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right_expr),
-            };
+            }
         }
         Ok(expr)
     }
 
-    // brief: factor -> unary ( ( "/" | "*") unary ) *
+    // brief: binary -> unary ( operator unary ) *, by precedence climbing over BINARY_OPERATORS.
+    //        Only operators at least as tight as min_precedence are taken at this level.
     // input:
     // output:
-    fn factor(&mut self) -> Result<Expr, String> {
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, String> {
         let mut expr = self.unary()?;
 
-        while self.match_tokens(&[TokenType::Slash, TokenType::Star]) {
-            let operator = self.previous();
-            let right_expr = self.unary()?;
+        while let Some((precedence, associativity)) = binary_operator(&self.peek().token_type) {
+            if precedence < min_precedence {
+                break;
+            }
+            let operator = self.advance();
+            let right_expr = match associativity {
+                Associativity::Left => self.binary(precedence + 1)?,
+                Associativity::Right => self.binary(precedence)?,
+            };
 
             expr = Expr::Binary {
                 left: Box::new(expr),
@@ -548,13 +1034,6 @@ impl Parser {
         })
     }
 
-    // brief: arguments -> expression ( "," expression ) *
-    // input:
-    // output
-    fn arguments(&mut self) {
-        todo!()
-    }
-
     // brief: primary -> NUMBER | STRING | "true" | "false" | "nil" | "(" expression ")" | Idetifier
     // input:
     // output:
@@ -572,7 +1051,14 @@ impl Parser {
                 value: ExprLiteral::Nil,
             })
         } else if self.match_tokens(&[TokenType::String]) {
-            if let Some(LiterialValue::StringValue(v)) = self.previous().literial {
+            // Adjacent strings like "a" "b" are joined here, the Scanner keeps one token per literal.
+            if let Some(LiterialValue::StringValue(mut v)) = self.previous().literial {
+                while self.match_tokens(&[TokenType::String]) {
+                    match self.previous().literial {
+                        Some(LiterialValue::StringValue(next)) => v.push_str(&next),
+                        _ => break,
+                    }
+                }
                 return Ok(Expr::Literal {
                     value: ExprLiteral::StringLiteral(v),
                 });
@@ -583,10 +1069,19 @@ impl Parser {
                 self.peek().lexeme
             ))
         } else if self.match_tokens(&[TokenType::Number]) {
-            if let Some(LiterialValue::FloatValue(v)) = self.previous().literial {
-                return Ok(Expr::Literal {
-                    value: ExprLiteral::NumberLiteral(v),
-                });
+            match self.previous().literial {
+                Some(LiterialValue::FloatValue(v)) => {
+                    return Ok(Expr::Literal {
+                        value: ExprLiteral::NumberLiteral(v),
+                    });
+                }
+                #[cfg(feature = "bignum")]
+                Some(LiterialValue::BigIntValue(v)) => {
+                    return Ok(Expr::Literal {
+                        value: ExprLiteral::BigIntLiteral(v),
+                    });
+                }
+                _ => {}
             }
             Err(format!(
                 "Error occur at parsering Number at line {} in {}, Maybe an error from Scanner.",
@@ -594,10 +1089,42 @@ impl Parser {
                 self.peek().lexeme
             ))
         } else if self.match_tokens(&[TokenType::Identifier]) {
+            if self.previous().lexeme == DISCARD && !self.check(TokenType::Equal) {
+                return Err(format!(
+                    "Parsering error occurs for cannot read discard '_' at line {}.",
+                    self.previous().line_number
+                ));
+            }
             Ok(Expr::Variable {
                 name: self.previous(),
             })
+        } else if self.match_tokens(&[TokenType::If]) {
+            self.if_expression()
+        } else if self.match_tokens(&[TokenType::LeftBrace]) {
+            self.block_expression()
+        } else if self.match_tokens(&[TokenType::Let]) {
+            let name = self.consume(TokenType::Identifier)?;
+            self.consume(TokenType::Equal)?;
+            let initializer = self.expression()?;
+            self.consume(TokenType::In)?;
+            Ok(Expr::LetIn {
+                name,
+                initializer: Box::new(initializer),
+                body: Box::new(self.expression()?),
+            })
+        } else if self.match_tokens(&[TokenType::When]) {
+            self.when_expression()
+        } else if self.match_tokens(&[TokenType::Lazy]) {
+            Ok(Expr::Lazy {
+                keyword: self.previous(),
+                expression: Box::new(self.expression()?),
+            })
         } else if self.match_tokens(&[TokenType::LeftParen]) {
+            if let Some(operator) = self.operator_value() {
+                return Ok(Expr::Literal {
+                    value: ExprLiteral::FunctionLiteral(Box::new(MyOperator::new(operator))),
+                });
+            }
             let expr = self.expression()?;
             let _ = self.consume(TokenType::RightParen)?; // Consume the RightParen.
             Ok(Expr::Grouping {
@@ -632,6 +1159,53 @@ impl Parser {
     //     }
     // }
 
+    // brief: operatorValue -> "(" operator ")", for the operators in BINARY_OPERATORS.
+    //        The "(" is consumed already, the operator and ")" are consumed only if both are there.
+    // input:
+    // output: The operator token.
+    fn operator_value(&mut self) -> Option<Token> {
+        let is_operator = binary_operator(&self.peek().token_type).is_some();
+        if !is_operator || !self.check_next(TokenType::RightParen) {
+            return None;
+        }
+        let operator = self.advance();
+        self.advance(); // Consume the RightParen.
+        Some(operator)
+    }
+
+    // brief: whenExpr -> "when" "{" ( expression "=>" expression "," ) * ( "else" "=>" expression ) ? "}"
+    //        The "," after the last branch may be left out.
+    // input:
+    // output:
+    fn when_expression(&mut self) -> Result<Expr, String> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftBrace)?;
+
+        let mut branches = vec![];
+        let mut else_branch = None;
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if self.match_tokens(&[TokenType::Else]) {
+                self.consume(TokenType::FatArrow)?;
+                else_branch = Some(Box::new(self.expression()?));
+                self.match_tokens(&[TokenType::Comma]);
+                break; // else is the last one.
+            }
+            let condition = self.expression()?;
+            self.consume(TokenType::FatArrow)?;
+            branches.push((condition, self.expression()?));
+            if !self.match_tokens(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace)?;
+
+        Ok(Expr::When {
+            keyword,
+            branches,
+            else_branch,
+        })
+    }
+
     // brief: Consume the current token, if tokentype matched.
     // input:
     // output:
@@ -648,6 +1222,17 @@ impl Parser {
         }
     }
 
+    // brief: Consume the ";" ending a statement, which may be left out before a closing "}",
+    //        so that { print 1.0 } is fine but { print 1.0 print 2.0 } is still an error.
+    // input:
+    // output:
+    fn consume_terminator(&mut self) -> Result<(), String> {
+        if !self.check(TokenType::RightBrace) {
+            self.consume(TokenType::Semicolon)?;
+        }
+        Ok(())
+    }
+
     // brief: Synchronize to give up the error code untill find a Unerror Defination..
     // input:
     // output:
@@ -665,7 +1250,13 @@ impl Parser {
                 | TokenType::If
                 | TokenType::While
                 | TokenType::Print
-                | TokenType::Return => return,
+                | TokenType::Return
+                | TokenType::Assert
+                | TokenType::Debug
+                | TokenType::Match
+                | TokenType::Break
+                | TokenType::Unset
+                | TokenType::Repeat => return,
 
                 _ => {
                     self.advance();
@@ -704,6 +1295,16 @@ impl Parser {
         self.tokens.get(self.current).unwrap().clone()
     }
 
+    // brief: Whether the token after the current one is of token_type.
+    // input:
+    // output:
+    fn check_next(&self, token_type: TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => token.token_type == token_type,
+            None => false,
+        }
+    }
+
     // brief: return current token and self.current ++
     // input:
     // output:
@@ -730,6 +1331,29 @@ impl Parser {
     }
 }
 
+// brief: Names and doc comments of the documented let / fn at the top level, in source order.
+// input:
+// output:
+pub fn extract_docs(statements: &[Stmt]) -> Vec<(String, String)> {
+    let mut docs = vec![];
+    for statement in statements {
+        match statement {
+            Stmt::Let {
+                name,
+                doc: Some(doc),
+                ..
+            }
+            | Stmt::Function {
+                name,
+                doc: Some(doc),
+                ..
+            } => docs.push((name.lexeme.clone(), doc.clone())),
+            _ => {}
+        }
+    }
+    docs
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -739,7 +1363,7 @@ mod tests {
         let sources = "1.0 * 3.0 * 2.0 + 2.0 * 4.0 == 11.0".to_string();
         let mut scan = Scanner::new(sources);
 
-        let tok = scan.scan_tokens().unwrap();
+        let _tok = scan.scan_tokens().unwrap();
 
         // let pas = Parser::new(tok).parse().unwrap().two_string();
 
@@ -949,5 +1573,175 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn parser_test_strict_blocks() {
+        let single = "let a = 1.0; if (a) print a; while (a) a = nil; for (;a;) a = nil;";
+        let braced = "let a = 1.0; if (a) { print a; } else if (a) { print a; } else { a = nil; } while (a) { a = nil; } for (;a;) { a = nil; }";
+
+        let tok = Scanner::new(single.to_string()).scan_tokens().unwrap();
+        let err = Parser::new(tok.clone())
+            .with_strict_blocks(true)
+            .parse()
+            .unwrap_err();
+        assert!(err.contains("expected '{'"));
+        assert!(Parser::new(tok).parse().is_ok()); // Permissive by default.
+
+        let tok = Scanner::new(braced.to_string()).scan_tokens().unwrap();
+        assert!(Parser::new(tok.clone())
+            .with_strict_blocks(true)
+            .parse()
+            .is_ok());
+        assert!(Parser::new(tok).parse().is_ok());
+
+        let tok = Scanner::new("if (true) {} else print 1.0;".to_string())
+            .scan_tokens()
+            .unwrap();
+        assert!(Parser::new(tok).with_strict_blocks(true).parse().is_err());
+    }
+
+    #[test]
+    fn parser_test_adjacent_strings() {
+        let tok = Scanner::new("print \"foo\" \n \"bar\"; print \"a\" + x;".to_string())
+            .scan_tokens()
+            .unwrap();
+        let pas = Parser::new(tok).parse().unwrap();
+
+        match &pas[0] {
+            Stmt::Print { values, .. } => assert_eq!(
+                values[0],
+                Expr::Literal {
+                    value: ExprLiteral::StringLiteral("foobar".to_string())
+                }
+            ),
+            _ => panic!("expected a print statement"),
+        }
+        let printed = match &pas[1] {
+            Stmt::Print { values, .. } => &values[0],
+            _ => panic!("expected a print statement"),
+        };
+        match printed {
+            Expr::Binary { left, right, .. } => {
+                assert_eq!(
+                    **left,
+                    Expr::Literal {
+                        value: ExprLiteral::StringLiteral("a".to_string())
+                    }
+                );
+                assert!(matches!(**right, Expr::Variable { .. }));
+            }
+            _ => panic!("expected a binary expression"),
+        }
+    }
+
+    #[test]
+    fn parser_test_condition_assign() {
+        let parse = |sources: &str, check: ConditionCheck| {
+            let tok = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+            let mut parser = Parser::new(tok).with_condition_assign(check);
+            let result = parser.parse();
+            (result, parser.warnings().len())
+        };
+
+        let (result, _) = parse("let x = 0.0; if (x = 1.0) {}", ConditionCheck::Error);
+        assert!(result.unwrap_err().contains("Assignment used as condition"));
+        let (result, _) = parse("let x = 0.0; while (x = nil) {}", ConditionCheck::Error);
+        assert!(result.is_err());
+
+        let (result, warnings) = parse("let x = 0.0; if (x = 1.0) {}", ConditionCheck::Warn);
+        assert!(result.is_ok());
+        assert_eq!(warnings, 1);
+
+        let (result, warnings) = parse("let x = 0.0; if (x == 1.0) {}", ConditionCheck::Warn);
+        assert!(result.is_ok());
+        assert_eq!(warnings, 0);
+        assert!(
+            parse("let x = 0.0; if (x == 1.0) {}", ConditionCheck::Error)
+                .0
+                .is_ok()
+        );
+
+        // Explicit parentheses mean it's intended.
+        assert!(
+            parse("let x = 0.0; if ((x = 1.0)) {}", ConditionCheck::Error)
+                .0
+                .is_ok()
+        );
+        // Default is Allow.
+        assert!(parse("let x = 0.0; if (x = 1.0) {}", ConditionCheck::Allow)
+            .0
+            .is_ok());
+    }
+
+    #[test]
+    fn parser_test_optional_semicolon() {
+        let parse = |sources: &str| {
+            let tok = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+            Parser::new(tok).parse()
+        };
+
+        assert!(parse("{ print 1.0 }").is_ok());
+        assert!(parse("fn f(a) { let b = a; return b }").is_ok());
+        assert!(parse("fn f() { return }").is_ok());
+        assert!(parse("{ print 1.0; a = 2.0 }").is_ok());
+
+        assert!(parse("{ print 1.0 print 2.0 }").is_err());
+        assert!(parse("{ let a = 1.0 print a; }").is_err());
+        assert!(parse("print 1.0").is_err()); // Only a "}" ends a statement.
+    }
+
+    #[test]
+    fn parser_test_docs() {
+        let sources = "/// The answer.\nlet answer = 42.0;\n\n/**\n * Add two numbers.\n *\n * Returns a + b.\n */\nfn add(a, b) = a + b;\n// not a doc\nfn plain() {}\n/// Cached.\n/// Twice.\n@memo fn slow(x) { /// inner\n let y = x; return y; }\n/// dropped\nprint add(1.0, /* two */ 2.0);\nlet b = /** three */ 3.0;";
+        let tok = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+        let statements = Parser::new(tok).parse().unwrap();
+        assert_eq!(
+            extract_docs(&statements),
+            vec![
+                ("answer".to_string(), "The answer.".to_string()),
+                (
+                    "add".to_string(),
+                    "Add two numbers.\n\nReturns a + b.".to_string()
+                ),
+                ("slow".to_string(), "Cached.\nTwice.".to_string()),
+            ]
+        );
+    }
+    #[test]
+    fn parser_test_precedence_table() {
+        // Trees given by the equality / comparision / term / factor functions before the table.
+        for (source, tree) in [
+            ("1.0 + 2.0 * 3.0", "( 1 + ( 2 * 3 ) )"),
+            ("1.0 * 2.0 + 3.0", "( ( 1 * 2 ) + 3 )"),
+            ("1.0 - 2.0 - 3.0", "( ( 1 - 2 ) - 3 )"),
+            ("8.0 / 4.0 / 2.0", "( ( 8 / 4 ) / 2 )"),
+            ("1.0 + 2.0 - 3.0 + 4.0", "( ( ( 1 + 2 ) - 3 ) + 4 )"),
+            ("1.0 < 2.0 == 3.0 > 4.0", "( ( 1 < 2 ) == ( 3 > 4 ) )"),
+            ("a <= b + c * d >= e", "( ( a <= ( b + ( c * d ) ) ) >= e )"),
+            ("a == b != c == d", "( ( ( a == b ) != c ) == d )"),
+            ("-a * -b - !c", "( ( ( - a ) * ( - b ) ) - ( ! c ) )"),
+            ("(1.0 + 2.0) * 3.0", "( ( ( 1 + 2 ) ) * 3 )"),
+            (
+                "a + b * c - d / e * f",
+                "( ( a + ( b * c ) ) - ( ( d / e ) * f ) )",
+            ),
+            ("a < b < c", "( ( a < b ) < c )"),
+            ("a and b == c or d", "( ( a and ( b == c ) ) or d )"),
+            ("x = a + b * c", "( x = ( a + ( b * c ) ) )"),
+            (
+                "f(a + b) * g(c) - h()",
+                "( ( f( ( a + b ) ) * g( c ) ) - h(  ) )",
+            ),
+            ("\"a\" + \"b\" == \"ab\"", "( ( a + b ) == ab )"),
+            ("a |> f + 1.0", "( f + 1 )( a )"),
+            ("1.0 >= 1.0 + 2.0 == 4.0", "( ( 1 >= ( 1 + 2 ) ) == 4 )"),
+        ] {
+            let tok = Scanner::new(format!("{};", source)).scan_tokens().unwrap();
+            match &Parser::new(tok).parse().unwrap()[0] {
+                Stmt::Expression(expr) => assert_eq!(expr.two_string(), tree, "{}", source),
+                other => panic!("{:?}", other),
+            }
+        }
+    }
 }
 // cargo test some-keyword --  --nocapture
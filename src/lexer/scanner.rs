@@ -2,12 +2,26 @@ use std::{collections::HashMap, num::ParseFloatError};
 
 use super::token::{LiterialValue, Token, TokenType};
 
+const TAB_WIDTH: usize = 8;
+
+// Numbers about the scanned source, for tools.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanStats {
+    pub tokens: HashMap<TokenType, usize>, // how many tokens of each kind, Eof is left out.
+    pub keywords: usize,                   // tokens which are keywords, like let or while.
+    pub lines: usize,
+    pub comments: usize,
+}
+
 pub struct Scanner {
     source: String,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
+    indent_lint: bool,     // warn about lines indented with both tabs and spaces.
+    warnings: Vec<String>, // diagnostics which don't stop scanning.
+    comments: usize,       // "//" and "/* */" comments, doc comments included.
 
     keywords: HashMap<&'static str, TokenType>,
 }
@@ -20,11 +34,51 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            indent_lint: false,
+            warnings: vec![],
+            comments: 0,
 
             keywords: Self::get_keyword_hashmap(),
         }
     }
 
+    // brief: Warn about lines whose indentation mixes tabs and spaces, since they line up
+    //        differently depending on the tab width. Default is off.
+    // input:
+    // output:
+    pub fn with_indent_lint(mut self, lint: bool) -> Self {
+        self.indent_lint = lint;
+        self
+    }
+
+    // brief: Warnings of the last scan_tokens() call.
+    // input:
+    // output:
+    pub fn warnings(&self) -> &Vec<String> {
+        &self.warnings
+    }
+
+    // brief: Statistics of the tokens scanned by scan_tokens().
+    // input:
+    // output:
+    pub fn stats(&self) -> ScanStats {
+        let mut stats = ScanStats {
+            lines: self.source.lines().count(),
+            comments: self.comments,
+            ..Default::default()
+        };
+        for token in &self.tokens {
+            if token.token_type == TokenType::Eof {
+                continue;
+            }
+            *stats.tokens.entry(token.token_type.clone()).or_insert(0) += 1;
+            if self.keywords.contains_key(token.lexeme.as_str()) {
+                stats.keywords += 1;
+            }
+        }
+        stats
+    }
+
     pub fn get_keyword_hashmap() -> HashMap<&'static str, TokenType> {
         HashMap::from([
             ("and", TokenType::And),
@@ -43,6 +97,15 @@ impl Scanner {
             ("true", TokenType::True),
             ("let", TokenType::Let),
             ("while", TokenType::While),
+            ("assert", TokenType::Assert),
+            ("debug", TokenType::Debug),
+            ("match", TokenType::Match),
+            ("break", TokenType::Break),
+            ("lazy", TokenType::Lazy),
+            ("in", TokenType::In),
+            ("unset", TokenType::Unset),
+            ("when", TokenType::When),
+            ("repeat", TokenType::Repeat),
         ])
     }
 
@@ -51,6 +114,8 @@ impl Scanner {
     // output: Convert self.source into Vec<String>
     pub fn scan_tokens(&mut self) -> Result<Vec<Token>, String> {
         let mut scan_errors = vec![];
+        self.warnings.clear();
+        self.check_indentation();
 
         // Scan the source String.
         while !self.is_at_end() {
@@ -64,12 +129,14 @@ impl Scanner {
             }
         }
 
-        // Add an EOF.
+        // Add an EOF, just past the last character.
+        self.start = self.current;
         self.tokens.push(Token {
             token_type: TokenType::Eof,
             lexeme: "EOF".to_string(),
             literial: None,
             line_number: self.line,
+            column: self.column(),
         });
 
         // Return all thErrors.
@@ -87,7 +154,7 @@ impl Scanner {
     }
 
     fn is_at_end(&self) -> bool {
-        self.current >= self.source.len()
+        self.current >= self.source.chars().count() // current counts chars, not bytes.
     }
 
     // brief: match token and add token to self, used by scan_tokens.
@@ -108,6 +175,21 @@ impl Scanner {
             '+' => self.add_token(TokenType::Plus),
             ';' => self.add_token(TokenType::Semicolon),
             '*' => self.add_token(TokenType::Star),
+            '@' => self.add_token(TokenType::At),
+            ':' => self.add_token(TokenType::Colon),
+            '\'' => {
+                if !self.is_alpha(self.peek()) {
+                    return Err(format!("Unexpected character at line: {}", self.line));
+                }
+                while self.is_alpha_and_digit(self.peek()) {
+                    self.advance();
+                }
+                let name = self.text(self.start + 1, self.current);
+                self.add_token_with_literial(
+                    TokenType::Label,
+                    Some(LiterialValue::IdentifierVal(name)),
+                );
+            }
             '!' => {
                 if self.second_operator_match('=') {
                     self.add_token(TokenType::BangEqual);
@@ -129,9 +211,18 @@ impl Scanner {
                     self.add_token(TokenType::Less);
                 }
             }
+            '|' => {
+                if self.second_operator_match('>') {
+                    self.add_token(TokenType::PipeArrow);
+                } else {
+                    return Err(format!("Unexpected character at line: {}", self.line));
+                }
+            }
             '=' => {
                 if self.second_operator_match('=') {
                     self.add_token(TokenType::EqualEqual);
+                } else if self.second_operator_match('>') {
+                    self.add_token(TokenType::FatArrow);
                 } else {
                     self.add_token(TokenType::Equal);
                 }
@@ -139,9 +230,23 @@ impl Scanner {
 
             '/' => {
                 if self.second_operator_match('/') {
+                    self.comments += 1;
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                    // "///" is a doc comment, "////..." lines are just comments.
+                    let text = self.text(self.start, self.current);
+                    if text.starts_with("///") && !text.starts_with("////") {
+                        let doc = text[3..].strip_prefix(' ').unwrap_or(&text[3..]);
+                        let doc = doc.trim_end().to_string();
+                        self.add_token_with_literial(
+                            TokenType::Doc,
+                            Some(LiterialValue::StringValue(doc)),
+                        );
+                    }
+                } else if self.second_operator_match('*') {
+                    self.comments += 1;
+                    self.find_a_block_comment()?;
                 } else {
                     self.add_token(TokenType::Slash);
                 }
@@ -151,6 +256,7 @@ impl Scanner {
 
             '\n' => {
                 self.line += 1;
+                self.check_indentation();
             }
 
             '"' => {
@@ -158,7 +264,9 @@ impl Scanner {
             }
 
             _ => {
-                if self.is_digit(temp_char) {
+                if temp_char == '0' && (self.peek() == 'x' || self.peek() == 'X') {
+                    self.find_a_hex_number()?;
+                } else if self.is_digit(temp_char) {
                     self.find_a_number()?;
                 } else if self.is_alpha(temp_char) {
                     self.find_an_identifier()?;
@@ -191,15 +299,33 @@ impl Scanner {
     // input:
     // output:
     fn add_token_with_literial(&mut self, token_type: TokenType, literial: Option<LiterialValue>) {
-        let text = self.source[self.start..self.current].to_string();
+        let text = self.text(self.start, self.current);
         self.tokens.push(Token {
             token_type,
             lexeme: text,
             literial,
             line_number: self.line,
+            column: self.column(),
         });
     }
 
+    // brief: 1-based column of self.start, a tab advances to the next multiple of TAB_WIDTH like terminals do.
+    // input:
+    // output:
+    fn column(&self) -> usize {
+        let mut column = 0;
+        for c in self.source.chars().take(self.start) {
+            if c == '\n' {
+                column = 0;
+            } else if c == '\t' {
+                column += TAB_WIDTH - column % TAB_WIDTH;
+            } else {
+                column += 1;
+            }
+        }
+        column + 1
+    }
+
     // brief: wether the second character matched.(conditional advanced.)
     // input:
     // output:
@@ -219,7 +345,9 @@ impl Scanner {
     // output: Err or Ok
     fn find_a_string(&mut self) -> Result<(), String> {
         while self.peek() != '"' && !self.is_at_end() {
-            self.advance();
+            if self.advance() == '\n' {
+                self.line += 1; // strings may span lines.
+            }
         }
 
         if self.is_at_end() {
@@ -228,11 +356,77 @@ impl Scanner {
 
         self.advance(); // consume the second ".
 
-        let value = self.source[(self.start + 1)..(self.current - 1)].to_string();
+        let value = self.text(self.start + 1, self.current - 1);
         self.add_token_with_literial(TokenType::String, Some(LiterialValue::StringValue(value)));
         Ok(())
     }
 
+    // brief: Check the indentation of the line starting at self.current, if the lint is on.
+    //        Blank lines are ignored, and so are lines inside strings and block comments.
+    // input:
+    // output:
+    fn check_indentation(&mut self) {
+        if !self.indent_lint {
+            return;
+        }
+        let indent: String = self
+            .source
+            .chars()
+            .skip(self.current) // current counts chars.
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        let blank = matches!(
+            self.source
+                .chars()
+                .nth(self.current + indent.chars().count()),
+            None | Some('\n' | '\r')
+        );
+        if !blank && indent.contains(' ') && indent.contains('\t') {
+            self.warnings.push(format!(
+                "Mixed tabs and spaces in indentation at line: {}.",
+                self.line
+            ));
+        }
+    }
+
+    // brief: Skip a "/* */" comment, "/** */" is a doc comment whose lines are kept without
+    //        the leading " * ". Comments don't nest.
+    // input:
+    // output: Err or Ok
+    fn find_a_block_comment(&mut self) -> Result<(), String> {
+        let is_doc = self.peek() == '*' && self.peek_next() != '/';
+        while !self.is_at_end() && (self.peek() != '*' || self.peek_next() != '/') {
+            if self.advance() == '\n' {
+                self.line += 1;
+            }
+        }
+
+        if self.is_at_end() {
+            return Err(String::from("Unterminated Comment Error!"));
+        }
+
+        self.advance(); // consume the "*/".
+        self.advance();
+
+        if is_doc {
+            let lines: Vec<String> = self
+                .text(self.start + 3, self.current - 2)
+                .lines()
+                .map(|line| {
+                    let line = line.trim();
+                    let line = line.strip_prefix('*').unwrap_or(line);
+                    line.strip_prefix(' ')
+                        .unwrap_or(line)
+                        .trim_end()
+                        .to_string()
+                })
+                .collect();
+            let doc = lines.join("\n").trim_matches('\n').to_string();
+            self.add_token_with_literial(TokenType::Doc, Some(LiterialValue::StringValue(doc)));
+        }
+        Ok(())
+    }
+
     // brief: Do sth when find an Number, and check whether legal.
     // input:
     // output: Err or Ok
@@ -242,6 +436,26 @@ impl Scanner {
         while self.is_digit(self.peek()) {
             self.advance();
         }
+
+        // BigInt literal like 123n, only digits are allowed before 'n'.
+        #[cfg(feature = "bignum")]
+        if self.peek() == 'n' {
+            self.advance();
+            self.check_number_boundary()?;
+            return match self.text(self.start, self.current - 1).parse() {
+                Ok(v) => {
+                    self.add_token_with_literial(
+                        TokenType::Number,
+                        Some(LiterialValue::BigIntValue(v)),
+                    );
+                    Ok(())
+                }
+                Err(_) => Err(String::from("Parse Error!")),
+            };
+        }
+
+        self.check_number_boundary()?;
+
         if self.peek() == '.' && self.is_digit(self.peek_next()) {
             // consume '.'
             self.advance();
@@ -252,7 +466,9 @@ impl Scanner {
         } else {
             return Err(String::from("digit Error!"));
         }
-        let value: Result<f64, ParseFloatError> = self.source[self.start..self.current].parse();
+        self.check_number_boundary()?;
+
+        let value: Result<f64, ParseFloatError> = self.text(self.start, self.current).parse();
         match value {
             Ok(v) => {
                 self.add_token_with_literial(TokenType::Number, Some(LiterialValue::FloatValue(v)));
@@ -263,6 +479,101 @@ impl Scanner {
         }
     }
 
+    // brief: Do sth when find a hex float like 0x1.8p3, which is 0x1.8 * 2^3 == 12.0.
+    //        The binary exponent "p" is optional for an integer like 0xff, but a fraction needs it,
+    //        as in C. The value is rounded to the nearest f64 once, also for subnormals like 0x1p-1074.
+    // input:
+    // output: Err or Ok
+    fn find_a_hex_number(&mut self) -> Result<(), String> {
+        self.advance(); // consume 'x'.
+
+        // The digits are mantissa * 2^exponent, the ones which don't fit in the mantissa only
+        // matter for rounding, so it's enough to know if any of them is not zero.
+        let mut mantissa = 0_u64;
+        let mut exponent = 0_i64;
+        let mut sticky = false;
+        let mut digits = 0;
+        let mut fraction = false;
+        loop {
+            if let Some(d) = self.peek().to_digit(16) {
+                if mantissa >> 56 == 0 {
+                    mantissa = mantissa * 16 + d as u64;
+                    exponent -= if fraction { 4 } else { 0 };
+                } else {
+                    sticky |= d != 0;
+                    exponent += if fraction { 0 } else { 4 };
+                }
+                digits += 1;
+            } else if self.peek() == '.' && !fraction {
+                fraction = true;
+            } else {
+                break;
+            }
+            self.advance();
+        }
+        if digits == 0 {
+            return Err(format!(
+                "Malformed hex number, no digits at line: {}, column: {}.",
+                self.line,
+                self.column()
+            ));
+        }
+
+        if self.peek() == 'p' || self.peek() == 'P' {
+            self.advance();
+            let negative = self.peek() == '-';
+            if self.peek() == '-' || self.peek() == '+' {
+                self.advance();
+            }
+            let mut power = 0_i64;
+            let mut power_digits = 0;
+            while let Some(d) = self.peek().to_digit(10) {
+                self.advance();
+                power = (power * 10 + d as i64).min(1 << 20); // far beyond the range of f64 anyway.
+                power_digits += 1;
+            }
+            if power_digits == 0 {
+                return Err(format!(
+                    "Malformed hex number exponent at line: {}, column: {}.",
+                    self.line,
+                    self.column()
+                ));
+            }
+            exponent += if negative { -power } else { power };
+        } else if fraction {
+            return Err(format!(
+                "Malformed hex number, a fraction needs a 'p' exponent at line: {}, column: {}.",
+                self.line,
+                self.column()
+            ));
+        }
+
+        self.check_number_boundary()?;
+
+        let value = hex_float_value(mantissa, exponent, sticky);
+        self.add_token_with_literial(TokenType::Number, Some(LiterialValue::FloatValue(value)));
+        Ok(())
+    }
+
+    // brief: A number can't run straight into a letter, so 3abc is an error instead of 3 and abc.
+    //        The rest of the word is consumed to go on scanning after it. Letters before digits
+    //        like abc3 are an identifier anyway.
+    // input:
+    // output: Err or Ok
+    fn check_number_boundary(&mut self) -> Result<(), String> {
+        if !self.is_alpha(self.peek()) {
+            return Ok(());
+        }
+        while self.is_alpha_and_digit(self.peek()) {
+            self.advance();
+        }
+        Err(format!(
+            "Malformed number {} at line: {}.",
+            self.text(self.start, self.current),
+            self.line
+        ))
+    }
+
     // brief: Do sth when find an Identifier, or keyword.
     // input:
     // output: Ok
@@ -272,10 +583,10 @@ impl Scanner {
             self.advance();
         }
 
-        let text = &self.source[self.start..self.current];
+        let text = self.text(self.start, self.current);
 
-        if self.keywords.contains_key(text) {
-            if let Some(ty) = self.keywords.get(text) {
+        if self.keywords.contains_key(text.as_str()) {
+            if let Some(ty) = self.keywords.get(text.as_str()) {
                 self.add_token(ty.clone());
             }
         } else {
@@ -296,6 +607,13 @@ impl Scanner {
         c.is_ascii_alphabetic() || c == '_'
     }
 
+    // brief: The chars from start to end, they are counted in chars like self.start and self.current.
+    // input:
+    // output:
+    fn text(&self, start: usize, end: usize) -> String {
+        self.source.chars().skip(start).take(end - start).collect()
+    }
+
     // brief: Return current char.
     // input:
     // output:
@@ -311,6 +629,42 @@ impl Scanner {
     }
 }
 
+// brief: mantissa * 2^exponent rounded to the nearest f64, ties to even. sticky is true if some
+//        nonzero digits after the mantissa were dropped, so the value is a bit more than that.
+// input:
+// output:
+fn hex_float_value(mantissa: u64, exponent: i64, sticky: bool) -> f64 {
+    if mantissa == 0 {
+        return 0.0;
+    }
+    let bits = 64 - mantissa.leading_zeros() as i64;
+    let top = exponent + bits - 1; // the value is in [2^top, 2^(top + 1)).
+    let precision = if top >= -1022 { 53 } else { 53 - (-1022 - top) }; // subnormals have less.
+    let dropped = bits - precision;
+
+    let (mut kept, mut exponent) = (mantissa, exponent);
+    if dropped > 64 {
+        kept = 0; // less than half of the smallest subnormal.
+    } else if dropped > 0 {
+        let rest = mantissa as u128 & ((1_u128 << dropped) - 1);
+        let half = 1_u128 << (dropped - 1);
+        kept = (mantissa as u128 >> dropped) as u64;
+        if rest > half || (rest == half && (sticky || kept % 2 == 1)) {
+            kept += 1;
+        }
+        exponent += dropped;
+    }
+
+    // kept fits in 53 bits and the result is on the f64 grid, so every step here is exact.
+    let mut value = kept as f64;
+    while exponent != 0 {
+        let step = exponent.clamp(-1000, 1000);
+        value *= 2.0_f64.powi(step as i32);
+        exponent -= step;
+    }
+    value
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -320,7 +674,7 @@ mod tests {
         let sources = "(())".to_string();
         let mut scan = Scanner::new(sources);
 
-        let res = scan.scan_tokens().unwrap();
+        let _res = scan.scan_tokens().unwrap();
 
         //dbg!(res);
     }
@@ -447,6 +801,187 @@ mod tests {
             }
         }
     }
+    #[test]
+    fn handle_hex_float() {
+        let number = |sources: &str| {
+            let res = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+            assert_eq!(res.len(), 2);
+            res[0].literial.clone()
+        };
+        assert_eq!(number("0x1p4"), Some(LiterialValue::FloatValue(16.0)));
+        assert_eq!(number("0x1.8p1"), Some(LiterialValue::FloatValue(3.0)));
+        assert_eq!(number("0x1.8p3"), Some(LiterialValue::FloatValue(12.0)));
+        assert_eq!(number("0X10P-4"), Some(LiterialValue::FloatValue(1.0)));
+        assert_eq!(number("0xff"), Some(LiterialValue::FloatValue(255.0)));
+
+        let err = Scanner::new("\n0x1.8p;".to_string())
+            .scan_tokens()
+            .unwrap_err();
+        assert!(err.contains("Malformed hex number exponent at line: 2, column: 1."));
+        let err = Scanner::new("a = 0x.p1;".to_string())
+            .scan_tokens()
+            .unwrap_err();
+        assert!(err.contains("Malformed hex number, no digits at line: 1, column: 5."));
+        assert!(Scanner::new("0x1.8".to_string()).scan_tokens().is_err());
+
+        // Rounded once to the nearest f64, in the subnormal range too.
+        assert_eq!(number("0x1p-1074"), Some(LiterialValue::FloatValue(5e-324)));
+        assert_eq!(number("0x1p-1075"), Some(LiterialValue::FloatValue(0.0)));
+        assert_eq!(
+            number("0x1.8p-1074"),
+            Some(LiterialValue::FloatValue(1e-323))
+        );
+        assert_eq!(
+            number("0x1.fffffffffffffp1023"),
+            Some(LiterialValue::FloatValue(f64::MAX))
+        );
+        assert_eq!(
+            number("0x1p1024"),
+            Some(LiterialValue::FloatValue(f64::INFINITY))
+        );
+        // 2^53 + 1 is halfway, it goes to the even 2^53, and any bit after it rounds up instead.
+        assert_eq!(
+            number("0x20000000000001"),
+            Some(LiterialValue::FloatValue(9007199254740992.0))
+        );
+        assert_eq!(
+            number("0x20000000000001000000000001p-48"),
+            Some(LiterialValue::FloatValue(9007199254740994.0))
+        );
+    }
+
+    #[test]
+    fn handle_number_boundary() {
+        let err = Scanner::new("let a = 1.0;\n3abc;".to_string())
+            .scan_tokens()
+            .unwrap_err();
+        assert!(err.contains("Malformed number 3abc at line: 2."));
+
+        let err = Scanner::new("3.0e5".to_string()).scan_tokens().unwrap_err();
+        assert!(err.contains("Malformed number 3.0e5 at line: 1."));
+        assert!(Scanner::new("0x1g".to_string()).scan_tokens().is_err());
+
+        for sources in ["abc3", "a3b", "_1"] {
+            let res = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+            assert_eq!(res.len(), 2);
+            assert_eq!(res[0].token_type, TokenType::Identifier);
+            assert_eq!(res[0].lexeme, sources);
+        }
+    }
+
+    #[test]
+    fn handle_column_with_tabs() {
+        let sources = "let a;\n\tb = 1.0;\n  \tc\tx\nabcdefg\ty".to_string();
+        let tokens = Scanner::new(sources).scan_tokens().unwrap();
+        let columns: Vec<(String, usize, usize)> = tokens
+            .iter()
+            .map(|t| (t.lexeme.clone(), t.line_number, t.column))
+            .collect();
+        assert_eq!(columns[0], ("let".to_string(), 1, 1));
+        assert_eq!(columns[1], ("a".to_string(), 1, 5));
+        assert_eq!(columns[3], ("b".to_string(), 2, 9)); // the tab takes 8 columns.
+        assert_eq!(columns[4], ("=".to_string(), 2, 11));
+        assert_eq!(columns[7], ("c".to_string(), 3, 9)); // the tab only fills up to 8.
+        assert_eq!(columns[8], ("x".to_string(), 3, 17));
+        assert_eq!(columns[9], ("abcdefg".to_string(), 4, 1));
+        assert_eq!(columns[10], ("y".to_string(), 4, 9));
+    }
+    #[test]
+    fn handle_doc_comments() {
+        let sources = "//// line\n/// one\n/* two\n */ /** three\n  * four */ /**/ a".to_string();
+        let tokens = Scanner::new(sources).scan_tokens().unwrap();
+        let docs: Vec<(TokenType, Option<LiterialValue>)> = tokens
+            .iter()
+            .map(|t| (t.token_type.clone(), t.literial.clone()))
+            .collect();
+        assert_eq!(
+            docs[..2],
+            [
+                (
+                    TokenType::Doc,
+                    Some(LiterialValue::StringValue("one".to_string()))
+                ),
+                (
+                    TokenType::Doc,
+                    Some(LiterialValue::StringValue("three\nfour".to_string()))
+                ),
+            ]
+        );
+        assert_eq!(tokens[2].lexeme, "a");
+        assert_eq!(tokens[2].line_number, 5);
+
+        assert!(Scanner::new("/* a".to_string()).scan_tokens().is_err());
+
+        // Non-ASCII comments, the tokens after them keep their text and position.
+        let tokens = Scanner::new("// café\n/// naïve ✓\nprint \"é\" + a;".to_string())
+            .scan_tokens()
+            .unwrap();
+        assert_eq!(
+            tokens[0].literial,
+            Some(LiterialValue::StringValue("naïve ✓".to_string()))
+        );
+        assert_eq!(tokens[1].lexeme, "print");
+        assert_eq!(
+            tokens[2].literial,
+            Some(LiterialValue::StringValue("é".to_string()))
+        );
+        assert_eq!((tokens[4].lexeme.as_str(), tokens[4].column), ("a", 13));
+    }
+    #[test]
+    fn handle_eof_position() {
+        let eof = |sources: &str| {
+            let tokens = Scanner::new(sources.to_string()).scan_tokens().unwrap();
+            let last = tokens.last().unwrap().clone();
+            assert_eq!(last.token_type, TokenType::Eof);
+            (last.line_number, last.column)
+        };
+        assert_eq!(eof(""), (1, 1));
+        assert_eq!(eof("let a;\nprint a;"), (2, 9));
+        assert_eq!(eof("let a;\nprint a;\n"), (3, 1));
+        assert_eq!(eof("let a;\n\tprint a;"), (2, 17));
+        assert_eq!(eof("let a = \"x\ny\";\n// end"), (3, 7));
+        assert_eq!(eof("/* a\n b */"), (2, 6));
+    }
+    #[test]
+    fn handle_indent_lint() {
+        let warnings = |sources: &str, lint: bool| {
+            let mut scan = Scanner::new(sources.to_string()).with_indent_lint(lint);
+            scan.scan_tokens().unwrap();
+            scan.warnings().clone()
+        };
+        let sources =
+            "fn f() {\n\t let a = 1.0;\n    let b = \"\n \tin a string\";\n\t\tprint a;\n  \t\n}";
+        assert_eq!(
+            warnings(sources, true),
+            vec!["Mixed tabs and spaces in indentation at line: 2.".to_string()]
+        );
+        assert!(warnings(sources, false).is_empty());
+        assert_eq!(
+            warnings("  \tlet a;", true),
+            vec!["Mixed tabs and spaces in indentation at line: 1.".to_string()]
+        );
+        assert!(warnings("{\n\tlet a;\n\t{\n\t\tlet b;\n\t}\n}\n    let c;", true).is_empty());
+
+        // Non-ASCII text before the line doesn't move it.
+        assert_eq!(
+            warnings("// é\nlet s = \"ünï\";\n \tlet a;\n\tlet b;", true),
+            vec!["Mixed tabs and spaces in indentation at line: 3.".to_string()]
+        );
+    }
+    #[test]
+    fn handle_stats() {
+        let sources = "// adds one\nfn inc(x) {\n    return x + 1.0; /* one */\n}\nlet a = inc(2.0);\nprint a;\n";
+        let mut scan = Scanner::new(sources.to_string());
+        scan.scan_tokens().unwrap();
+        let stats = scan.stats();
+        assert_eq!(stats.lines, 6);
+        assert_eq!(stats.comments, 2);
+        assert_eq!(stats.keywords, 4); // fn, return, let, print
+        assert_eq!(stats.tokens[&TokenType::Identifier], 6);
+        assert_eq!(stats.tokens[&TokenType::Number], 2);
+        assert_eq!(stats.tokens[&TokenType::Semicolon], 3);
+        assert!(!stats.tokens.contains_key(&TokenType::Eof));
+    }
     // cargo test <unique signature: keyword> --  --nocapture
 
     // #[test]
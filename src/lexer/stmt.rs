@@ -1,12 +1,21 @@
-use super::{expr::Expr, token::Token};
+use super::{
+    expr::{Expr, ExprLiteral},
+    token::Token,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Stmt {
     Expression(Expr),
-    Print(Expr),
+    Print {
+        keyword: Token,
+        values: Vec<Expr>,
+        sep: Option<Expr>, // between values, " " if None.
+        end: Option<Expr>, // after the last value, "\n" if None.
+    },
     Let {
         name: Token,
         initializer: Expr,
+        doc: Option<String>, // text of the "///" or "/** */" comment before it.
     },
     Block {
         statements: Vec<Stmt>,
@@ -24,10 +33,83 @@ pub enum Stmt {
         name: Token,
         params: Vec<Token>,
         body: Box<Stmt>,
+        memo: bool,          // annotated with @memo.
+        doc: Option<String>, // text of the "///" or "/** */" comment before it.
     },
 
     Return {
         keyword: Token,
         value: Expr,
     },
+    Assert {
+        keyword: Token,
+        condition: Expr,
+    },
+    Debug {
+        keyword: Token,
+    },
+    Labeled {
+        label: Token,    // 'name
+        body: Box<Stmt>, // a block or a loop.
+    },
+    Break {
+        keyword: Token,
+        label: Token, // the labeled statement to leave.
+    },
+    Repeat {
+        keyword: Token,
+        count: Expr,     // evaluated once, a whole number >= 0.
+        body: Box<Stmt>, // Stmt::Block
+    },
+    Unset {
+        keyword: Token,
+        name: Token, // removed from the nearest scope which has it.
+    },
+    Match {
+        keyword: Token,
+        value: Expr,
+        arms: Vec<MatchArm>, // tried in order, only the first matched one runs.
+    },
+}
+
+impl Stmt {
+    // brief: Line of the first token of the statement.
+    // input:
+    // output: None if it has no token, like `1.0;`.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Stmt::Expression(expr) => expr.line(),
+            Stmt::Print { keyword, .. }
+            | Stmt::Return { keyword, .. }
+            | Stmt::Assert { keyword, .. }
+            | Stmt::Debug { keyword }
+            | Stmt::Break { keyword, .. }
+            | Stmt::Repeat { keyword, .. }
+            | Stmt::Unset { keyword, .. }
+            | Stmt::Match { keyword, .. } => Some(keyword.line_number),
+            Stmt::Let { name, .. } | Stmt::Function { name, .. } => Some(name.line_number),
+            Stmt::Labeled { label, .. } => Some(label.line_number),
+            Stmt::Block { statements } => statements.iter().find_map(|v| v.line()),
+            Stmt::If { condition, .. } | Stmt::While { condition, .. } => condition.line(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>, // `pattern if condition =>`, the bound name is visible in it.
+    pub body: Stmt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    // `number n`, matches by the kind of the value and binds it to the name in the arm.
+    Type {
+        type_name: Token,
+        binding: Option<Token>,
+    },
+    Literal(ExprLiteral), // `1.0`, `"a"`, `true`, `nil`, compared with "==".
+    Binding(Token),       // `n`, matches anything and binds it, mostly used with a guard.
+    Wildcard,             // `_`, matches anything.
 }
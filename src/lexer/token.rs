@@ -4,6 +4,7 @@ pub struct Token {
     pub lexeme: String,
     pub literial: Option<LiterialValue>,
     pub line_number: usize,
+    pub column: usize, // 1-based, see Scanner::column for how tabs are counted.
 }
 
 impl Token {
@@ -12,12 +13,14 @@ impl Token {
         lexeme: String,
         literial: Option<LiterialValue>,
         line_number: usize,
+        column: usize,
     ) -> Self {
         Self {
             token_type,
             lexeme,
             literial,
             line_number,
+            column,
         }
     }
 
@@ -32,9 +35,11 @@ pub enum LiterialValue {
     FloatValue(f64),
     StringValue(String),
     IdentifierVal(String),
+    #[cfg(feature = "bignum")]
+    BigIntValue(num_bigint::BigInt),
 }
 
-#[derive(Debug, Clone, PartialEq)] // Todo: Partialeq
+#[derive(Debug, Clone, PartialEq, Eq, Hash)] // Todo: Partialeq
 pub enum TokenType {
     // Single-character tokens.
     LeftParen,
@@ -48,6 +53,8 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    At,
+    Colon,
     // One or two character tokens.
     Bang,
     BangEqual,
@@ -57,10 +64,13 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    PipeArrow,
+    FatArrow,
     // Literials.
     Identifier,
     String,
     Number,
+    Label, // 'name, the name without "'" is in the literial.
     // Keywords.
     And,
     CLass,
@@ -78,6 +88,17 @@ pub enum TokenType {
     True,
     Let,
     While,
+    Assert,
+    Debug,
+    Match,
+    Break,
+    Lazy,
+    In,
+    Unset,
+    When,
+    Repeat,
+    // Comments.
+    Doc, // "///" or "/** */", the text is in the literial.
     // Eof.
     Eof,
 }
@@ -0,0 +1,167 @@
+use std::{fmt::Display, str::FromStr};
+
+// One step of a run recorded by Interpreter::with_trace(), values are kept as their printed text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent {
+    Statement {
+        line: Option<usize>, // None for a statement without a token, like `1.0;`.
+    },
+    Define {
+        line: usize, // let
+        name: String,
+        value: String,
+    },
+    Assign {
+        line: usize, // =
+        name: String,
+        value: String,
+    },
+    Print {
+        line: usize,
+        text: String,
+    },
+    Call {
+        line: usize,
+        name: String,
+    },
+}
+
+// One event per line, which TraceEvent::from_str() reads back, so a trace can be saved and replayed:
+//     line 4: statement        statement (no line)
+//     line 4: let a = "1"      line 5: a = "2"
+//     line 6: print "2\n"      line 5: call double
+// Values and printed text are quoted and escaped like Rust strings, so they stay on one line.
+impl Display for TraceEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceEvent::Statement { line: Some(line) } => write!(f, "line {}: statement", line),
+            TraceEvent::Statement { line: None } => write!(f, "statement"),
+            TraceEvent::Define { line, name, value } => {
+                write!(f, "line {}: let {} = {:?}", line, name, value)
+            }
+            TraceEvent::Assign { line, name, value } => {
+                write!(f, "line {}: {} = {:?}", line, name, value)
+            }
+            TraceEvent::Print { line, text } => write!(f, "line {}: print {:?}", line, text),
+            TraceEvent::Call { line, name } => write!(f, "line {}: call {}", line, name),
+        }
+    }
+}
+
+impl FromStr for TraceEvent {
+    type Err = String;
+
+    // brief: Read back one line written by Display.
+    // input:
+    // output: Err with the line if it is not a trace event.
+    fn from_str(event: &str) -> Result<Self, String> {
+        let malformed = || format!("Malformed trace event: {}", event);
+        if event == "statement" {
+            return Ok(TraceEvent::Statement { line: None });
+        }
+        let (line, rest) = event
+            .strip_prefix("line ")
+            .and_then(|v| v.split_once(": "))
+            .ok_or_else(malformed)?;
+        let line: usize = line.parse().map_err(|_| malformed())?;
+
+        // Names have no spaces, so "let a = .." and "print ".."" are not assignments.
+        if let Some((name, value)) = rest.split_once(" = ").filter(|(n, _)| !n.contains(' ')) {
+            return Ok(TraceEvent::Assign {
+                line,
+                name: name.to_string(),
+                value: unquote(value).ok_or_else(malformed)?,
+            });
+        }
+        if rest == "statement" {
+            return Ok(TraceEvent::Statement { line: Some(line) });
+        }
+        if let Some((name, value)) = rest.strip_prefix("let ").and_then(|v| v.split_once(" = ")) {
+            return Ok(TraceEvent::Define {
+                line,
+                name: name.to_string(),
+                value: unquote(value).ok_or_else(malformed)?,
+            });
+        }
+        if let Some(text) = rest.strip_prefix("print ") {
+            return Ok(TraceEvent::Print {
+                line,
+                text: unquote(text).ok_or_else(malformed)?,
+            });
+        }
+        if let Some(name) = rest.strip_prefix("call ") {
+            return Ok(TraceEvent::Call {
+                line,
+                name: name.to_string(),
+            });
+        }
+        Err(malformed())
+    }
+}
+
+// brief: Undo the quoting of {:?} for a String.
+// input:
+// output: None if it is not a quoted string.
+fn unquote(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        text.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'u' => {
+                let hex: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+            }
+            other => other, // \\ \" \'
+        });
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TraceEvent;
+
+    #[test]
+    fn test_trace_round_trip() {
+        let events = vec![
+            TraceEvent::Statement { line: None },
+            TraceEvent::Statement { line: Some(4) },
+            TraceEvent::Define {
+                line: 4,
+                name: "a".to_string(),
+                value: "x = \"1\"".to_string(),
+            },
+            TraceEvent::Assign {
+                line: 5,
+                name: "call".to_string(),
+                value: "2".to_string(),
+            },
+            TraceEvent::Print {
+                line: 6,
+                text: "a\tb\\\n\u{7}".to_string(),
+            },
+            TraceEvent::Call {
+                line: 5,
+                name: "double".to_string(),
+            },
+        ];
+        for event in events {
+            let line = event.to_string();
+            assert!(!line.contains('\n'));
+            assert_eq!(line.parse::<TraceEvent>(), Ok(event));
+        }
+
+        assert!("line x: statement".parse::<TraceEvent>().is_err());
+        assert!("line 1: a = 2".parse::<TraceEvent>().is_err()); // not quoted.
+        assert!("hello".parse::<TraceEvent>().is_err());
+    }
+}
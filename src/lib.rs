@@ -0,0 +1,5 @@
+pub mod lexer;
+
+pub use lexer::interpreter::Interpreter;
+pub use lexer::parser::Parser;
+pub use lexer::scanner::Scanner;
@@ -1,31 +1,46 @@
+use noah::{Interpreter, Parser, Scanner};
 use std::fs;
-mod lexer;
-use lexer::interpreter::Interpreter;
-use lexer::parser::Parser;
-use lexer::scanner::Scanner;
 
-fn run_file(file_path: &String) -> Result<(), String> {
-    let contents = fs::read_to_string(file_path).unwrap();
+// brief: Run a script.
+// input: summary: run it as a test file, see Interpreter::with_assert_summary().
+// output:
+fn run_file(file_path: &String, summary: bool) -> Result<(), String> {
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Error occur when reading {}: {}.", file_path, e))?;
 
     let mut scan = Scanner::new(contents);
 
-    let tok = scan.scan_tokens().unwrap();
+    let tok = scan.scan_tokens()?;
 
-    let pas = Parser::new(tok).parse().unwrap();
+    let pas = Parser::new(tok).parse()?;
 
     //    dbg!(pas);
 
-    let _ = Interpreter::new().interpreter(&pas)?; // return 1 ???
+    let _ = Interpreter::new()
+        .with_assert_summary(summary)
+        .interpreter(&pas)?; // return 1 ???
     Ok(())
 }
 
+// Usage: noah [--summary] [file], the file is test.py by default.
 fn main() {
-    match run_file(&String::from("test.py")) {
+    let mut summary = false;
+    let mut file_path = String::from("test.py");
+    for arg in std::env::args().skip(1) {
+        if arg == "--summary" {
+            summary = true;
+        } else {
+            file_path = arg;
+        }
+    }
+
+    match run_file(&file_path, summary) {
         Ok(()) => {
             println!("[     PASS!    ] ---> Compile Successfully!!!");
         }
         Err(v) => {
             println!("[    Error!    ] ---> {}", v);
+            std::process::exit(1);
         }
     }
 }